@@ -0,0 +1,457 @@
+//! On-disk cache for slow, rarely-changing fields.
+//!
+//! Values live in `$XDG_CACHE_HOME/ghostfetch/cache.json`. Each entry records
+//! when it was stored and an invalidation key (usually a file mtime); an entry
+//! is reused only while it is younger than its TTL and the key still matches.
+
+use crate::info::Sysroot;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Bumped whenever the on-disk layout or a cached value's shape changes.
+const CACHE_VERSION: u32 = 1;
+
+/// How the cache is used for this run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Read fresh entries and store new ones.
+    Normal,
+    /// Recompute every field this run reads and store the results
+    /// (`--refresh-cache`). Entries of fields it doesn't read are kept.
+    Refresh,
+    /// Neither read nor write (`--no-cache`).
+    Disabled,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<String, Entry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    stored_at: u64,
+    value: serde_json::Value,
+}
+
+pub struct Cache {
+    path: Option<PathBuf>,
+    mode: CacheMode,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    /// Entries stored by a module's thread, held back until the module
+    /// finishes in time. See [`set_owner`].
+    staged: HashMap<&'static str, Vec<(String, Entry)>>,
+    dirty: bool,
+}
+
+thread_local! {
+    static OWNER: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// Attribute entries stored from the current thread to `owner`. They are
+/// staged rather than stored until [`Cache::commit`] is called for `owner`,
+/// so a module that misses the deadline leaves nothing half-done behind.
+pub fn set_owner(owner: &'static str) {
+    OWNER.with(|cell| cell.set(Some(owner)));
+}
+
+impl Cache {
+    /// Open the cache at the default location.
+    pub fn open(mode: CacheMode) -> Cache {
+        Cache::open_at(default_path(), mode)
+    }
+
+    /// Open the cache at `path`. A missing, unreadable or outdated file simply
+    /// starts an empty cache.
+    pub fn open_at(path: Option<PathBuf>, mode: CacheMode) -> Cache {
+        // Refresh loads the file too, so saving doesn't drop the entries of
+        // modules that didn't run
+        let entries = match (&path, mode) {
+            (Some(path), CacheMode::Normal | CacheMode::Refresh) => fs::read_to_string(path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<CacheFile>(&content).ok())
+                .filter(|file| file.version == CACHE_VERSION)
+                .map(|file| file.entries)
+                .unwrap_or_default(),
+            _ => HashMap::new(),
+        };
+
+        Cache {
+            path,
+            mode,
+            state: Mutex::new(State {
+                entries,
+                ..State::default()
+            }),
+        }
+    }
+
+    /// A cache that never reads or writes.
+    #[cfg(test)]
+    pub fn disabled() -> Cache {
+        Cache::open_at(None, CacheMode::Disabled)
+    }
+
+    /// Return the cached value for `field` if it is younger than `ttl` and was
+    /// stored under the same invalidation `key`; otherwise compute it, store
+    /// it, and return it.
+    ///
+    /// `None`, empty lists and empty strings are returned but not stored: they
+    /// usually mean a helper failed or was killed, and caching that would hide
+    /// the field for the whole TTL.
+    pub fn get_or_insert_with<T, F>(&self, field: &str, ttl: Duration, key: &str, compute: F) -> T
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> T,
+    {
+        if self.mode == CacheMode::Disabled {
+            return compute();
+        }
+
+        let now = now_secs();
+        // Refresh never reads an entry, but still overwrites it
+        let cached = self.state.lock().ok().and_then(|state| {
+            state
+                .entries
+                .get(field)
+                .filter(|_| self.mode == CacheMode::Normal)
+                .filter(|e| e.key == key && now.saturating_sub(e.stored_at) < ttl.as_secs())
+                .cloned()
+        });
+        if let Some(entry) = cached
+            && let Ok(value) = serde_json::from_value(entry.value)
+        {
+            return value;
+        }
+
+        let value = compute();
+        if let Ok(json) = serde_json::to_value(&value)
+            && worth_caching(&json)
+            && let Ok(mut state) = self.state.lock()
+        {
+            let entry = Entry {
+                key: key.to_string(),
+                stored_at: now,
+                value: json,
+            };
+            match OWNER.with(Cell::get) {
+                Some(owner) => state
+                    .staged
+                    .entry(owner)
+                    .or_default()
+                    .push((field.to_string(), entry)),
+                None => {
+                    state.entries.insert(field.to_string(), entry);
+                    state.dirty = true;
+                }
+            }
+        }
+        value
+    }
+
+    /// Store the entries staged by `owner`'s thread.
+    pub fn commit(&self, owner: &str) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let Some(staged) = state.staged.remove(owner) else {
+            return;
+        };
+        state.entries.extend(staged);
+        state.dirty = true;
+    }
+
+    /// Write the cache back to disk if anything changed.
+    ///
+    /// Writes to a temporary file and renames it into place so a concurrent
+    /// ghostfetch never reads a half-written cache.
+    pub fn save(&self) {
+        let Some(ref path) = self.path else {
+            return;
+        };
+        if self.mode == CacheMode::Disabled {
+            return;
+        }
+        let Ok(state) = self.state.lock() else {
+            return;
+        };
+        if !state.dirty {
+            return;
+        }
+
+        let file = CacheFile {
+            version: CACHE_VERSION,
+            entries: state.entries.clone(),
+        };
+        let Ok(json) = serde_json::to_string(&file) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let tmp = path.with_extension(format!("json.{}", std::process::id()));
+        if fs::write(&tmp, json).is_ok() && fs::rename(&tmp, path).is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+    }
+}
+
+fn worth_caching(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::String(s) => !s.is_empty(),
+        serde_json::Value::Array(items) => !items.is_empty(),
+        _ => true,
+    }
+}
+
+/// `$XDG_CACHE_HOME/ghostfetch/cache.json`, falling back to `~/.cache`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("ghostfetch").join("cache.json"))
+}
+
+/// Invalidation key built from the modification times of `paths`.
+///
+/// Missing paths contribute `-`, so a package database appearing or
+/// disappearing also invalidates the entry.
+pub fn mtime_key<P: AsRef<Path>>(paths: &[P]) -> String {
+    paths
+        .iter()
+        .map(|path| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos().to_string())
+                .unwrap_or_else(|| "-".to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Invalidation key for a command's version output: the resolved binary path
+/// plus its mtime, so upgrading or switching the program invalidates it.
+pub fn binary_key(program: &str) -> String {
+    match find_in_path(program) {
+        Some(path) => format!("{}@{}", path.display(), mtime_key(&[&path])),
+        None => format!("{}@-", program),
+    }
+}
+
+/// Invalidation key that changes on every reboot, for hardware facts that
+/// cannot change while the machine is running.
+pub fn boot_key(root: &Sysroot) -> String {
+    root.read_trimmed("/proc/sys/kernel/random/boot_id")
+        .unwrap_or_default()
+}
+
+/// Resolve `program` the way the shell would: as-is if it contains a slash,
+/// otherwise the first match in `$PATH`.
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return path.exists().then_some(path);
+    }
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|path| path.is_file())
+    })
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ghostfetch-cache-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("cache.json")
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let path = temp_cache_path("round-trip");
+        let calls = Cell::new(0);
+        let compute = || {
+            calls.set(calls.get() + 1);
+            vec!["NVIDIA".to_string()]
+        };
+
+        let cache = Cache::open_at(Some(path.clone()), CacheMode::Normal);
+        let first: Vec<String> =
+            cache.get_or_insert_with("gpus", Duration::from_secs(60), "k1", compute);
+        cache.save();
+
+        let cache = Cache::open_at(Some(path.clone()), CacheMode::Normal);
+        let second: Vec<String> =
+            cache.get_or_insert_with("gpus", Duration::from_secs(60), "k1", compute);
+        assert_eq!(first, second);
+        assert_eq!(calls.get(), 1, "second run should hit the cache");
+
+        // A different invalidation key forces a recompute
+        let _: Vec<String> =
+            cache.get_or_insert_with("gpus", Duration::from_secs(60), "k2", compute);
+        assert_eq!(calls.get(), 2);
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_cache_expired_entry_is_recomputed() {
+        let cache = Cache::open_at(None, CacheMode::Normal);
+        let calls = Cell::new(0);
+        for _ in 0..2 {
+            let _: u32 = cache.get_or_insert_with("speed", Duration::ZERO, "", || {
+                calls.set(calls.get() + 1);
+                6000
+            });
+        }
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_refresh_mode_ignores_existing_entries() {
+        let path = temp_cache_path("refresh");
+        let cache = Cache::open_at(Some(path.clone()), CacheMode::Normal);
+        let _: u32 = cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 4800);
+        cache.save();
+
+        let cache = Cache::open_at(Some(path.clone()), CacheMode::Refresh);
+        let speed: u32 = cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 6000);
+        assert_eq!(speed, 6000);
+        cache.save();
+
+        let cache = Cache::open_at(Some(path.clone()), CacheMode::Normal);
+        let speed: u32 = cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 0);
+        assert_eq!(speed, 6000, "refresh should have rewritten the entry");
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_refresh_mode_keeps_fields_it_does_not_read() {
+        let path = temp_cache_path("refresh-keeps");
+        let cache = Cache::open_at(Some(path.clone()), CacheMode::Normal);
+        let _: u32 = cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 4800);
+        let _: String =
+            cache.get_or_insert_with("shell", Duration::from_secs(60), "", || "zsh".to_string());
+        cache.save();
+
+        let cache = Cache::open_at(Some(path.clone()), CacheMode::Refresh);
+        let _: u32 = cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 6000);
+        cache.save();
+
+        let cache = Cache::open_at(Some(path.clone()), CacheMode::Normal);
+        let shell: String =
+            cache.get_or_insert_with("shell", Duration::from_secs(60), "", String::new);
+        assert_eq!(shell, "zsh");
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_disabled_cache_never_writes() {
+        let path = temp_cache_path("disabled");
+        let cache = Cache::open_at(Some(path.clone()), CacheMode::Disabled);
+        let _: u32 = cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 6000);
+        cache.save();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_failures_are_not_cached() {
+        let cache = Cache::open_at(None, CacheMode::Normal);
+        let calls = Cell::new(0);
+        for _ in 0..2 {
+            let _: Option<String> =
+                cache.get_or_insert_with("memory_layout", Duration::from_secs(60), "", || {
+                    calls.set(calls.get() + 1);
+                    None
+                });
+            let _: Vec<String> =
+                cache.get_or_insert_with("gpus", Duration::from_secs(60), "", || {
+                    calls.set(calls.get() + 1);
+                    Vec::new()
+                });
+        }
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[test]
+    fn test_owned_entries_wait_for_commit() {
+        let cache = Cache::open_at(None, CacheMode::Normal);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                set_owner("memory");
+                let _: u32 =
+                    cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 6000);
+            });
+        });
+        let speed: u32 = cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 0);
+        assert_eq!(speed, 0, "staged entry should not be visible before commit");
+
+        let cache = Cache::open_at(None, CacheMode::Normal);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                set_owner("memory");
+                let _: u32 =
+                    cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 6000);
+            });
+        });
+        cache.commit("memory");
+        let speed: u32 = cache.get_or_insert_with("speed", Duration::from_secs(60), "", || 0);
+        assert_eq!(speed, 6000);
+    }
+
+    #[test]
+    fn test_boot_key_reads_through_sysroot() {
+        let dir = temp_cache_path("boot-key").with_file_name("root");
+        let random = dir.join("proc/sys/kernel/random");
+        fs::create_dir_all(&random).unwrap();
+        fs::write(
+            random.join("boot_id"),
+            "6f1c2d4e-0000-4000-8000-000000000001\n",
+        )
+        .unwrap();
+        assert_eq!(
+            boot_key(&Sysroot::new(dir.clone())),
+            "6f1c2d4e-0000-4000-8000-000000000001"
+        );
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn test_mtime_key_marks_missing_paths() {
+        assert_eq!(mtime_key(&["/nonexistent/ghostfetch"]), "-");
+        assert_ne!(mtime_key(&["/"]), "-");
+    }
+}
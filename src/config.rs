@@ -0,0 +1,315 @@
+use colored::Color;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// User configuration loaded from `~/.config/ghostfetch/config.toml`.
+///
+/// Every field is optional; anything left out keeps the built-in default.
+/// Command-line flags are applied on top of this in `main`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Module keys to show, in display order. `None` means the default list.
+    pub modules: Option<Vec<String>>,
+    /// Show optional modules (same as `--all`).
+    pub all: bool,
+    /// Disable colors (same as `--no-color`).
+    pub no_color: bool,
+    /// Overall collection budget in milliseconds (same as `--timeout`).
+    pub timeout_ms: Option<u64>,
+    pub logo: LogoConfig,
+    pub colors: ColorConfig,
+    pub cpu: CpuConfig,
+    pub gpu: GpuConfig,
+    pub memory: MemoryConfig,
+    pub disks: DiskConfig,
+    pub public_ip: PublicIpConfig,
+    /// Label overrides keyed by module key, e.g. `cpu = "Processor"`.
+    pub labels: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogoConfig {
+    /// Show the ASCII logo (`false` is the same as `--off`).
+    pub enabled: bool,
+    /// Distro logo to use instead of the detected one (same as `--logo`).
+    pub name: Option<String>,
+    /// Custom ASCII art file (same as `--ascii`). A leading `~/` is expanded.
+    pub ascii: Option<String>,
+}
+
+impl Default for LogoConfig {
+    fn default() -> Self {
+        LogoConfig {
+            enabled: true,
+            name: None,
+            ascii: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorConfig {
+    /// Color for the title and labels. Defaults to the logo's color.
+    pub labels: Option<String>,
+    /// Color for the ASCII logo. Defaults to the distro's color.
+    pub logo: Option<String>,
+}
+
+/// Extra details on the CPU line. Core and thread counts are always shown.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CpuConfig {
+    /// Package temperature from hwmon.
+    pub temperature: bool,
+    /// Scaling governor and energy-performance preference.
+    pub governor: bool,
+    /// Usage sampled over a short interval.
+    pub usage: bool,
+}
+
+/// Extra details on each GPU line. All off by default: on NVIDIA each one
+/// means starting `nvidia-smi`, which is slow.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GpuConfig {
+    /// Total and used video memory.
+    pub vram: bool,
+    /// Kernel driver version.
+    pub driver_version: bool,
+    /// Current busy percentage.
+    pub load: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryConfig {
+    /// List slots and each module's size, type and speeds under the Memory
+    /// line.
+    pub detail: bool,
+}
+
+/// The Public IP lookup. Off unless `enabled`, since it is the only module
+/// that talks to the network.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PublicIpConfig {
+    pub enabled: bool,
+    /// Endpoint answering with the caller's address as plain text. Queried
+    /// once over IPv4 and once over IPv6. `http://` URLs are fetched
+    /// directly; `https://` ones through `curl`.
+    pub url: String,
+    /// Per-request limit covering name resolution, connecting and the
+    /// response.
+    pub timeout_ms: u64,
+}
+
+impl Default for PublicIpConfig {
+    fn default() -> Self {
+        PublicIpConfig {
+            enabled: false,
+            url: "https://api64.ipify.org".to_string(),
+            timeout_ms: 1000,
+        }
+    }
+}
+
+/// Which mounts the Disk lines show.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiskConfig {
+    /// Mount point globs to show. `*` also matches `/`, so `/srv*` covers
+    /// everything under `/srv`.
+    pub include: Vec<String>,
+    /// Mount point globs to leave out, applied after `include`.
+    pub exclude: Vec<String>,
+    /// Leave out tmpfs, overlay, squashfs and other virtual filesystems, and
+    /// anything mounted from a loop device.
+    pub hide_pseudo: bool,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        DiskConfig {
+            include: ["/", "/home*", "/data*", "/mnt*", "/media*"]
+                .map(String::from)
+                .to_vec(),
+            exclude: Vec::new(),
+            hide_pseudo: true,
+        }
+    }
+}
+
+impl Config {
+    /// Parse a config from TOML text.
+    pub fn parse(content: &str) -> Result<Config, String> {
+        toml::from_str(content).map_err(|e| format!("Invalid config: {}", e.message()))
+    }
+
+    /// Load the config file at `path`.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read config {}: {}", path.display(), e))?;
+        Config::parse(&content).map_err(|e| format!("{} ({})", e, path.display()))
+    }
+
+    /// Load the config from `explicit` if given, otherwise from the default
+    /// location. A missing default config is not an error; any other problem
+    /// is reported as a warning and the built-in defaults are used.
+    pub fn load_or_default(explicit: Option<&Path>) -> Config {
+        let path = match explicit {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Config::default(),
+            },
+        };
+
+        Config::load(&path).unwrap_or_else(|e| {
+            eprintln!("Warning: {}", e);
+            Config::default()
+        })
+    }
+
+    /// The label to show for a module, honoring `[labels]` overrides.
+    pub fn label<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.labels.get(key).map(String::as_str).unwrap_or(default)
+    }
+
+    /// Whether the module list names `key`. Modules get the resolved list,
+    /// with `--all` and the defaults already applied.
+    pub fn lists_module(&self, key: &str) -> bool {
+        self.modules
+            .as_ref()
+            .is_some_and(|keys| keys.iter().any(|k| k == key))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/ghostfetch/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("ghostfetch").join("config.toml"))
+}
+
+/// Expand a leading `~/` to the user's home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(home) = std::env::var_os("HOME")
+    {
+        return PathBuf::from(home).join(rest);
+    }
+    PathBuf::from(path)
+}
+
+/// Parse a color name ("cyan", "bright blue") or hex value ("#88c0d0").
+pub fn parse_color(name: &str) -> Option<Color> {
+    match name.parse() {
+        Ok(color) => Some(color),
+        Err(()) => {
+            eprintln!("Warning: unknown color '{}' in config", name);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_config_uses_defaults() {
+        let config = Config::parse("").unwrap();
+        assert!(config.modules.is_none());
+        assert!(config.logo.enabled);
+        assert!(!config.all);
+        assert!(config.labels.is_empty());
+        assert!(!config.gpu.vram);
+        assert!(!config.gpu.load);
+        assert!(!config.public_ip.enabled);
+    }
+
+    #[test]
+    fn test_full_config() {
+        let config = Config::parse(
+            r##"
+            modules = ["os_name", "kernel", "cpu"]
+            all = true
+
+            [logo]
+            name = "arch"
+
+            [colors]
+            labels = "#88c0d0"
+
+            [cpu]
+            temperature = true
+
+            [disks]
+            include = ["/", "/srv*", "/var/lib/*"]
+
+            [gpu]
+            vram = true
+            load = true
+
+            [public_ip]
+            enabled = true
+            url = "http://ip.internal.example/"
+
+            [labels]
+            cpu = "Processor"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.modules.as_deref(),
+            Some(
+                &[
+                    "os_name".to_string(),
+                    "kernel".to_string(),
+                    "cpu".to_string()
+                ][..]
+            )
+        );
+        assert!(config.all);
+        assert_eq!(config.logo.name.as_deref(), Some("arch"));
+        assert!(config.cpu.temperature);
+        assert!(!config.cpu.usage);
+        assert_eq!(config.disks.include.len(), 3);
+        assert!(config.disks.hide_pseudo);
+        assert!(config.gpu.vram);
+        assert!(config.gpu.load);
+        assert!(!config.gpu.driver_version);
+        assert!(config.public_ip.enabled);
+        assert_eq!(config.public_ip.url, "http://ip.internal.example/");
+        assert_eq!(config.public_ip.timeout_ms, 1000);
+        assert_eq!(config.label("cpu", "CPU"), "Processor");
+        assert_eq!(config.label("gpus", "GPU"), "GPU");
+        assert_eq!(
+            config.colors.labels.as_deref().and_then(parse_color),
+            Some(Color::TrueColor {
+                r: 0x88,
+                g: 0xc0,
+                b: 0xd0
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_field_is_rejected() {
+        let err = Config::parse("modles = []").unwrap_err();
+        assert!(err.contains("modles"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn test_load_missing_explicit_file() {
+        let err = Config::load(Path::new("/nonexistent/ghostfetch.toml")).unwrap_err();
+        assert!(err.contains("Cannot read config"));
+    }
+}
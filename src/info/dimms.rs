@@ -0,0 +1,471 @@
+//! Memory module layout: which slots are populated, with what.
+//!
+//! SMBIOS type 17 ("Memory Device") records are the source of truth. They can
+//! arrive as the raw table the kernel exports, as udev's decoded copy of it,
+//! or as `dmidecode` text; EDAC's sysfs view covers ECC machines without
+//! SMBIOS access. Every source is parsed into the same [`MemoryDevice`] list,
+//! so the parsers can be used on their own.
+
+use super::runner::CommandRunner;
+use super::sysroot::Sysroot;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+const MIB: u64 = 1024 * 1024;
+
+/// One memory slot.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryDevice {
+    /// Slot name printed on the board, e.g. `DIMM 1` or `A2`.
+    pub locator: String,
+    pub bank_locator: Option<String>,
+    /// `None` when the slot is empty.
+    pub size_bytes: Option<u64>,
+    /// `DDR5`, `LPDDR5`, ...
+    pub memory_type: Option<String>,
+    /// The speed the module is rated for (its SPD/JEDEC speed).
+    pub rated_speed_mts: Option<u32>,
+    /// The speed it is running at, e.g. with XMP/EXPO enabled.
+    pub configured_speed_mts: Option<u32>,
+    pub manufacturer: Option<String>,
+    pub part_number: Option<String>,
+}
+
+impl MemoryDevice {
+    pub fn is_populated(&self) -> bool {
+        self.size_bytes.is_some_and(|size| size > 0)
+    }
+}
+
+impl fmt::Display for MemoryDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.locator)?;
+        if let Some(ref bank) = self.bank_locator {
+            write!(f, " ({})", bank)?;
+        }
+        write!(f, ":")?;
+        if let Some(size) = self.size_bytes {
+            if size >= GIB {
+                write!(f, " {} GiB", size / GIB)?;
+            } else {
+                write!(f, " {} MiB", size / MIB)?;
+            }
+        }
+        if let Some(ref memory_type) = self.memory_type {
+            write!(f, " {}", memory_type)?;
+        }
+        match (self.configured_speed_mts, self.rated_speed_mts) {
+            (Some(configured), Some(rated)) if configured != rated => {
+                write!(f, " @ {} MT/s (rated {} MT/s)", configured, rated)
+            }
+            (Some(speed), _) | (None, Some(speed)) => write!(f, " @ {} MT/s", speed),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Slot count and the modules fitted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MemoryLayout {
+    /// `None` when the source only lists populated slots.
+    pub slots: Option<usize>,
+    /// Populated slots only.
+    pub modules: Vec<MemoryDevice>,
+}
+
+impl MemoryLayout {
+    fn from_devices(devices: Vec<MemoryDevice>, lists_empty_slots: bool) -> Option<MemoryLayout> {
+        if devices.is_empty() {
+            return None;
+        }
+        let slots = lists_empty_slots.then_some(devices.len());
+        let modules: Vec<MemoryDevice> = devices.into_iter().filter(|d| d.is_populated()).collect();
+        Some(MemoryLayout { slots, modules })
+    }
+
+    /// The speed the memory runs at: the configured speed when firmware
+    /// reports one, the rated speed otherwise.
+    pub fn speed_mts(&self) -> Option<u32> {
+        self.modules
+            .iter()
+            .find_map(|m| m.configured_speed_mts)
+            .or_else(|| self.modules.iter().find_map(|m| m.rated_speed_mts))
+    }
+}
+
+/// The module layout from the first source that has one.
+///
+/// The raw SMBIOS table and `dmidecode` both need root; udev's copy of the
+/// table and EDAC are readable by everyone.
+pub fn get_memory_layout(root: &Sysroot, cmd: &dyn CommandRunner) -> Option<MemoryLayout> {
+    let smbios = fs::read(root.path("/sys/firmware/dmi/tables/DMI"))
+        .ok()
+        .map(|table| parse_smbios_memory(&table))
+        .filter(|devices| !devices.is_empty())
+        .or_else(|| {
+            root.read_to_string("/run/udev/data/+dmi:id")
+                .ok()
+                .map(|db| parse_udev_memory(&db))
+                .filter(|devices| !devices.is_empty())
+        })
+        .or_else(|| {
+            cmd.run("dmidecode", &["-t", "memory"])
+                .map(|text| parse_dmidecode_memory(&text))
+                .filter(|devices| !devices.is_empty())
+        });
+    match smbios {
+        Some(devices) => MemoryLayout::from_devices(devices, true),
+        None => MemoryLayout::from_devices(read_edac_dimms(root), false),
+    }
+}
+
+const SMBIOS_MEMORY_DEVICE: u8 = 17;
+const SMBIOS_END_OF_TABLE: u8 = 127;
+
+/// Type 17 records from a raw SMBIOS structure table, as exported in
+/// `/sys/firmware/dmi/tables/DMI`.
+pub fn parse_smbios_memory(table: &[u8]) -> Vec<MemoryDevice> {
+    let mut devices = Vec::new();
+    let mut offset = 0;
+
+    while offset + 4 <= table.len() {
+        let kind = table[offset];
+        let length = table[offset + 1] as usize;
+        if length < 4 || offset + length > table.len() {
+            break;
+        }
+        let formatted = &table[offset..offset + length];
+
+        // Strings follow the formatted area and end with a double NUL
+        let strings_start = offset + length;
+        let mut end = strings_start;
+        while end + 1 < table.len() && !(table[end] == 0 && table[end + 1] == 0) {
+            end += 1;
+        }
+        let strings: Vec<&[u8]> = table[strings_start..end]
+            .split(|&b| b == 0)
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        if kind == SMBIOS_MEMORY_DEVICE {
+            devices.push(smbios_memory_device(formatted, &strings));
+        } else if kind == SMBIOS_END_OF_TABLE {
+            break;
+        }
+        offset = end + 2;
+    }
+
+    devices
+}
+
+fn smbios_memory_device(data: &[u8], strings: &[&[u8]]) -> MemoryDevice {
+    let byte = |at: usize| data.get(at).copied();
+    let word = |at: usize| Some(u16::from_le_bytes([byte(at)?, byte(at + 1)?]));
+    let dword = |at: usize| {
+        Some(u32::from_le_bytes([
+            byte(at)?,
+            byte(at + 1)?,
+            byte(at + 2)?,
+            byte(at + 3)?,
+        ]))
+    };
+    let string = |at: usize| {
+        let index = byte(at)? as usize;
+        let raw = strings.get(index.checked_sub(1)?)?;
+        let text = String::from_utf8_lossy(raw).trim().to_string();
+        (!text.is_empty() && !is_placeholder(&text)).then_some(text)
+    };
+    // 0xFFFF defers to a 32-bit field added in SMBIOS 3.3
+    let speed = |at: usize, extended: usize| match word(at)? {
+        0 => None,
+        0xFFFF => dword(extended).filter(|&s| s > 0),
+        speed => Some(speed as u32),
+    };
+
+    let size_bytes = match word(0x0C) {
+        None | Some(0) | Some(0xFFFF) => None,
+        Some(0x7FFF) => dword(0x1C).map(|mib| (mib & 0x7FFF_FFFF) as u64 * MIB),
+        Some(size) if size & 0x8000 != 0 => Some((size & 0x7FFF) as u64 * 1024),
+        Some(size) => Some(size as u64 * MIB),
+    };
+
+    MemoryDevice {
+        locator: string(0x10).unwrap_or_default(),
+        bank_locator: string(0x11),
+        size_bytes,
+        memory_type: byte(0x12).and_then(smbios_memory_type).map(str::to_string),
+        rated_speed_mts: speed(0x15, 0x54),
+        configured_speed_mts: speed(0x20, 0x58),
+        manufacturer: string(0x17),
+        part_number: string(0x1A),
+    }
+}
+
+/// SMBIOS memory type codes for the DRAM generations still in use.
+fn smbios_memory_type(code: u8) -> Option<&'static str> {
+    Some(match code {
+        0x12 => "DDR",
+        0x13 => "DDR2",
+        0x18 => "DDR3",
+        0x1A => "DDR4",
+        0x1B => "LPDDR",
+        0x1C => "LPDDR2",
+        0x1D => "LPDDR3",
+        0x1E => "LPDDR4",
+        0x20 => "HBM",
+        0x21 => "HBM2",
+        0x22 => "DDR5",
+        0x23 => "LPDDR5",
+        0x24 => "HBM3",
+        _ => return None,
+    })
+}
+
+/// Firmware fills unused string fields with these.
+fn is_placeholder(value: &str) -> bool {
+    matches!(
+        value,
+        "Unknown" | "Not Specified" | "Not Provided" | "None" | "To Be Filled By O.E.M."
+    )
+}
+
+/// `MEMORY_DEVICE_<n>_*` properties that systemd's `udev-dmi-memory-id`
+/// stores in `/run/udev/data/+dmi:id`.
+pub fn parse_udev_memory(db: &str) -> Vec<MemoryDevice> {
+    let mut devices: BTreeMap<usize, MemoryDevice> = BTreeMap::new();
+
+    for line in db.lines() {
+        let Some((key, value)) = line
+            .strip_prefix("E:MEMORY_DEVICE_")
+            .and_then(|property| property.split_once('='))
+        else {
+            continue;
+        };
+        let Some((index, field)) = key.split_once('_') else {
+            continue;
+        };
+        let Ok(index) = index.parse() else {
+            continue;
+        };
+        let device = devices.entry(index).or_default();
+        let text = || (!is_placeholder(value)).then(|| value.to_string());
+        match field {
+            "LOCATOR" => device.locator = value.to_string(),
+            "BANK_LOCATOR" => device.bank_locator = text(),
+            "SIZE" => device.size_bytes = value.parse().ok().filter(|&size| size > 0),
+            "TYPE" => device.memory_type = text(),
+            "SPEED_MTS" => device.rated_speed_mts = value.parse().ok(),
+            "CONFIGURED_SPEED_MTS" => device.configured_speed_mts = value.parse().ok(),
+            "MANUFACTURER" => device.manufacturer = text(),
+            "PART_NUMBER" => device.part_number = text(),
+            _ => {}
+        }
+    }
+
+    devices.into_values().collect()
+}
+
+/// `Memory Device` sections of `dmidecode -t memory` output.
+pub fn parse_dmidecode_memory(text: &str) -> Vec<MemoryDevice> {
+    let mut devices = Vec::new();
+    let mut current: Option<MemoryDevice> = None;
+
+    for line in text.lines() {
+        if line == "Memory Device" {
+            devices.extend(current.take());
+            current = Some(MemoryDevice::default());
+            continue;
+        }
+        if !line.starts_with('\t') {
+            devices.extend(current.take());
+            continue;
+        }
+        let (Some(device), Some((key, value))) = (current.as_mut(), line.trim().split_once(':'))
+        else {
+            continue;
+        };
+        let value = value.trim();
+        let text = || (!is_placeholder(value)).then(|| value.to_string());
+        // "6000 MT/s", or "6000 MHz" from dmidecode before 3.0
+        let speed = || value.split_whitespace().next()?.parse().ok();
+        match key {
+            "Locator" => device.locator = value.to_string(),
+            "Bank Locator" => device.bank_locator = text(),
+            "Size" => device.size_bytes = parse_dmidecode_size(value),
+            "Type" => device.memory_type = text(),
+            "Speed" => device.rated_speed_mts = speed(),
+            "Configured Memory Speed" | "Configured Clock Speed" => {
+                device.configured_speed_mts = speed()
+            }
+            "Manufacturer" => device.manufacturer = text(),
+            "Part Number" => device.part_number = text(),
+            _ => {}
+        }
+    }
+    devices.extend(current);
+
+    devices
+}
+
+/// `32 GB`, `16384 MB`, or `No Module Installed`.
+fn parse_dmidecode_size(value: &str) -> Option<u64> {
+    let (amount, unit) = value.split_once(' ')?;
+    let amount: u64 = amount.parse().ok()?;
+    let unit = match unit {
+        "kB" | "KB" => 1024,
+        "MB" => MIB,
+        "GB" => GIB,
+        "TB" => GIB * 1024,
+        _ => return None,
+    };
+    Some(amount * unit)
+}
+
+/// Populated DIMMs from `/sys/devices/system/edac/mc`. EDAC doesn't report
+/// empty slots or speeds.
+pub fn read_edac_dimms(root: &Sysroot) -> Vec<MemoryDevice> {
+    let Ok(controllers) = root.read_dir("/sys/devices/system/edac/mc") else {
+        return Vec::new();
+    };
+    let mut dimms: Vec<_> = controllers
+        .flatten()
+        .filter(|mc| mc.file_name().to_string_lossy().starts_with("mc"))
+        .filter_map(|mc| fs::read_dir(mc.path()).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("dimm") || name.starts_with("rank")
+        })
+        .map(|entry| entry.path())
+        .collect();
+    dimms.sort();
+
+    dimms
+        .into_iter()
+        .filter_map(|dimm| {
+            let read = |file: &str| {
+                fs::read_to_string(dimm.join(file))
+                    .ok()
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            };
+            let size_mib: u64 = read("size")?.parse().ok()?;
+            Some(MemoryDevice {
+                locator: read("dimm_label").or_else(|| read("dimm_location"))?,
+                size_bytes: Some(size_mib * MIB).filter(|&size| size > 0),
+                // "Unbuffered-DDR5", "Registered-DDR4"
+                memory_type: read("dimm_mem_type")
+                    .map(|t| t.rsplit('-').next().unwrap_or(&t).to_string())
+                    .filter(|t| t != "Unknown"),
+                ..MemoryDevice::default()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+
+    #[test]
+    fn test_parse_dmidecode_memory() {
+        let text = fixtures::commands("arch-desktop")
+            .run("dmidecode", &["-t", "memory"])
+            .unwrap();
+        let devices = parse_dmidecode_memory(&text);
+        assert_eq!(devices.len(), 4);
+        assert!(!devices[0].is_populated());
+        assert_eq!(devices[1].locator, "DIMM 1");
+        assert_eq!(devices[1].size_bytes, Some(32 * GIB));
+        assert_eq!(devices[1].memory_type.as_deref(), Some("DDR5"));
+        assert_eq!(devices[1].rated_speed_mts, Some(4800));
+        assert_eq!(devices[1].configured_speed_mts, Some(6000));
+        assert_eq!(devices[1].part_number.as_deref(), Some("F5-6000J3038F16G"));
+    }
+
+    #[test]
+    fn test_parse_smbios_memory_table() {
+        let table =
+            fs::read(fixtures::root("nixos-framework").path("/sys/firmware/dmi/tables/DMI"))
+                .unwrap();
+        let devices = parse_smbios_memory(&table);
+        assert_eq!(devices.len(), 2);
+        assert_eq!(
+            devices[0],
+            MemoryDevice {
+                locator: "DIMM 0".to_string(),
+                bank_locator: Some("P0 CHANNEL A".to_string()),
+                size_bytes: Some(16 * GIB),
+                memory_type: Some("DDR5".to_string()),
+                rated_speed_mts: Some(5600),
+                configured_speed_mts: Some(5600),
+                manufacturer: Some("Crucial Technology".to_string()),
+                part_number: Some("CT16G56C46S5.M8G1".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_memory_layout_sources() {
+        // udev's copy of the table
+        let machine = "ubuntu-thinkpad";
+        let layout =
+            get_memory_layout(&fixtures::root(machine), &fixtures::commands(machine)).unwrap();
+        assert_eq!(layout.slots, Some(2));
+        assert_eq!(layout.modules.len(), 2);
+        assert_eq!(layout.modules[0].memory_type.as_deref(), Some("LPDDR5"));
+        assert_eq!(layout.speed_mts(), Some(7467));
+
+        // dmidecode: configured speed wins over the rated one
+        let machine = "arch-desktop";
+        let layout =
+            get_memory_layout(&fixtures::root(machine), &fixtures::commands(machine)).unwrap();
+        assert_eq!((layout.slots, layout.modules.len()), (Some(4), 2));
+        assert_eq!(layout.speed_mts(), Some(6000));
+
+        // EDAC: sizes and types only
+        let machine = "gentoo-workstation";
+        let layout =
+            get_memory_layout(&fixtures::root(machine), &fixtures::commands(machine)).unwrap();
+        assert_eq!((layout.slots, layout.modules.len()), (None, 2));
+        assert_eq!(layout.modules[0].size_bytes, Some(32 * GIB));
+        assert_eq!(layout.modules[0].memory_type.as_deref(), Some("DDR5"));
+        assert_eq!(layout.speed_mts(), None);
+
+        let machine = "fedora-vm";
+        assert_eq!(
+            get_memory_layout(&fixtures::root(machine), &fixtures::commands(machine)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_memory_device_display() {
+        let device = MemoryDevice {
+            locator: "DIMM 1".to_string(),
+            size_bytes: Some(32 * GIB),
+            memory_type: Some("DDR5".to_string()),
+            rated_speed_mts: Some(4800),
+            configured_speed_mts: Some(6000),
+            ..MemoryDevice::default()
+        };
+        assert_eq!(
+            device.to_string(),
+            "DIMM 1: 32 GiB DDR5 @ 6000 MT/s (rated 4800 MT/s)"
+        );
+
+        let stock = MemoryDevice {
+            bank_locator: Some("P0 CHANNEL B".to_string()),
+            configured_speed_mts: Some(4800),
+            ..device
+        };
+        assert_eq!(
+            stock.to_string(),
+            "DIMM 1 (P0 CHANNEL B): 32 GiB DDR5 @ 4800 MT/s"
+        );
+    }
+}
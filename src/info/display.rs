@@ -1,25 +1,124 @@
-use super::helpers::{run_cmd, strip_ansi_codes};
+use super::edid::{Edid, HdrMetadata, VrrRange, lookup_pnp_id, parse_edid};
+use super::helpers::strip_ansi_codes;
+use super::runner::CommandRunner;
+use super::sysroot::Sysroot;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
+use std::path::Path;
 
 /// Monitor display information.
-#[derive(Clone)]
+///
+/// `resolution` and `refresh_rate` describe the active mode, or the preferred
+/// mode when only DRM knows about the output. The rest comes from the
+/// monitor's EDID and is `None` (or empty) when there isn't one.
+#[derive(Clone, Debug, Serialize)]
 pub struct MonitorInfo {
     pub name: String,
+    /// DRM connector name, e.g. `DP-2`.
+    pub connector: String,
     pub resolution: String,
     pub refresh_rate: String,
+    /// HDR is switched on for this output.
     pub hdr: bool,
+    /// Compositor scale factor.
+    pub scale: Option<f64>,
+    /// Output transform, using Wayland's names (`normal`, `90`, `flipped`, ...).
+    pub transform: Option<String>,
+    /// Variable refresh rate is switched on.
+    pub vrr: Option<bool>,
+    pub vendor: Option<String>,
+    pub serial: Option<String>,
+    pub year: Option<u16>,
+    pub width_cm: Option<f64>,
+    pub height_cm: Option<f64>,
+    pub diagonal_inches: Option<f64>,
+    /// Horizontal DPI at the active resolution.
+    pub dpi: Option<u32>,
+    /// The monitor's preferred mode, e.g. "3840x2160 @ 60 Hz".
+    pub native_mode: Option<String>,
+    /// What the monitor advertises, whether or not HDR is switched on.
+    pub hdr_metadata: Option<HdrMetadata>,
+    pub vrr_range: Option<VrrRange>,
+    pub colorimetry: Vec<String>,
 }
 
-pub fn get_de() -> String {
+impl MonitorInfo {
+    /// A monitor on `connector` in its active mode, named and filled in from
+    /// its decoded EDID when there is one.
+    pub fn new(
+        connector: &str,
+        resolution: String,
+        refresh_rate: String,
+        hdr: bool,
+        edid: Option<&Edid>,
+    ) -> Self {
+        let size_mm = edid.and_then(|e| e.size_mm);
+        let width_px = resolution
+            .split_once('x')
+            .and_then(|(w, _)| w.parse::<f64>().ok());
+        MonitorInfo {
+            name: edid
+                .and_then(|e| e.name.clone())
+                .unwrap_or_else(|| connector.to_string()),
+            connector: connector.to_string(),
+            resolution,
+            refresh_rate,
+            hdr,
+            scale: None,
+            transform: None,
+            vrr: None,
+            vendor: edid.and_then(|e| e.vendor.clone()),
+            serial: edid.and_then(|e| e.serial.clone()),
+            year: edid.and_then(|e| e.year),
+            width_cm: size_mm.map(|(w, _)| w as f64 / 10.0),
+            height_cm: size_mm.map(|(_, h)| h as f64 / 10.0),
+            diagonal_inches: size_mm.map(|(w, h)| {
+                let inches = ((w * w + h * h) as f64).sqrt() / 25.4;
+                (inches * 10.0).round() / 10.0
+            }),
+            dpi: match (size_mm, width_px) {
+                (Some((w, _)), Some(px)) if w > 0 => Some((px * 25.4 / w as f64).round() as u32),
+                _ => None,
+            },
+            native_mode: edid.and_then(|e| e.preferred_mode).map(|m| m.to_string()),
+            hdr_metadata: edid.and_then(|e| e.hdr.clone()),
+            vrr_range: edid.and_then(|e| e.vrr),
+            colorimetry: edid.map(|e| e.colorimetry.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for MonitorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}) {}", self.name, self.resolution)?;
+        // Outputs only seen through DRM may not have a known refresh rate
+        if !self.refresh_rate.is_empty() {
+            write!(f, " @ {}", self.refresh_rate)?;
+        }
+        if let Some(scale) = self.scale.filter(|&scale| scale != 1.0) {
+            write!(f, " (x{})", scale)?;
+        }
+        if let Some(inches) = self.diagonal_inches {
+            write!(f, " {}\"", inches)?;
+        }
+        if self.hdr {
+            write!(f, " [HDR]")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn get_de(cmd: &dyn CommandRunner) -> String {
     let de = std::env::var("XDG_CURRENT_DESKTOP")
         .or_else(|_| std::env::var("DESKTOP_SESSION"))
         .unwrap_or_else(|_| "Unknown".to_string());
 
     // Try to get version for KDE Plasma
     if (de.to_lowercase().contains("kde") || de.to_lowercase().contains("plasma"))
-        && let Some(output) = run_cmd("plasmashell", &["--version"])
+        && let Some(version) = cmd.run("plasmashell", &["--version"])
     {
-        let version = String::from_utf8_lossy(&output.stdout);
         for line in version.lines() {
             if line.contains("plasmashell")
                 && let Some(ver) = line.split_whitespace().last()
@@ -42,7 +141,7 @@ pub fn get_display_server() -> String {
     }
 }
 
-pub fn get_wm() -> String {
+pub fn get_wm(cmd: &dyn CommandRunner) -> String {
     let display_server = get_display_server();
 
     // Try to detect WM more accurately
@@ -73,8 +172,8 @@ pub fn get_wm() -> String {
                     "xmonad",
                     "spectrwm",
                 ];
-                if let Some(output) = run_cmd("ps", &["-e", "-o", "comm="]) {
-                    let procs = String::from_utf8_lossy(&output.stdout).to_lowercase();
+                if let Some(output) = cmd.run("ps", &["-e", "-o", "comm="]) {
+                    let procs = output.to_lowercase();
                     for wm in &wms {
                         if procs.contains(wm) {
                             return format!("{} ({})", wm, display_server);
@@ -91,30 +190,21 @@ pub fn get_wm() -> String {
     format!("{} ({})", wm, display_server)
 }
 
-pub fn get_terminal() -> String {
+/// Identify the terminal emulator, without querying its version.
+///
+/// Returns the display name, which is also the binary to run for
+/// `--version`, or `None` when no known terminal was found.
+pub fn find_terminal() -> Option<&'static str> {
     // Check for ghostty-specific environment variables (works even inside tmux)
     if std::env::var("GHOSTTY_BIN_DIR").is_ok() || std::env::var("GHOSTTY_RESOURCES_DIR").is_ok() {
-        if let Some(output) = run_cmd("ghostty", &["--version"]) {
-            let version = String::from_utf8_lossy(&output.stdout);
-            if let Some(line) = version.lines().next() {
-                return format!("ghostty {}", line.split_whitespace().last().unwrap_or(""));
-            }
-        }
-        return "ghostty".to_string();
+        return Some("ghostty");
     }
 
     // Check TERM_PROGRAM (set by many modern terminals)
-    if let Ok(term_prog) = std::env::var("TERM_PROGRAM") {
-        let term = term_prog.to_lowercase();
-        if term.contains("ghostty") {
-            if let Some(output) = run_cmd("ghostty", &["--version"]) {
-                let version = String::from_utf8_lossy(&output.stdout);
-                if let Some(line) = version.lines().next() {
-                    return format!("ghostty {}", line.split_whitespace().last().unwrap_or(""));
-                }
-            }
-            return "ghostty".to_string();
-        }
+    if let Ok(term_prog) = std::env::var("TERM_PROGRAM")
+        && term_prog.to_lowercase().contains("ghostty")
+    {
+        return Some("ghostty");
     }
 
     let terminals = [
@@ -148,30 +238,7 @@ pub fn get_terminal() -> String {
                     .to_lowercase();
                 for (proc_name, display_name) in &terminals {
                     if comm == *proc_name || comm.contains(proc_name) {
-                        // Try to get version
-                        if let Some(output) = run_cmd(display_name, &["--version"]) {
-                            let version = String::from_utf8_lossy(&output.stdout);
-                            if let Some(line) = version.lines().next() {
-                                // Extract version from line
-                                let line_parts: Vec<&str> = line.split_whitespace().collect();
-                                for part in line_parts {
-                                    if part
-                                        .chars()
-                                        .next()
-                                        .map(|c| c.is_ascii_digit())
-                                        .unwrap_or(false)
-                                        || part.starts_with('v')
-                                    {
-                                        return format!(
-                                            "{} {}",
-                                            display_name,
-                                            part.trim_start_matches('v')
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        return display_name.to_string();
+                        return Some(display_name);
                     }
                 }
                 ppid = parts[3].parse().unwrap_or(1);
@@ -184,7 +251,37 @@ pub fn get_terminal() -> String {
         }
     }
 
-    std::env::var("TERM").unwrap_or_else(|_| "Unknown".to_string())
+    None
+}
+
+pub fn get_terminal(cmd: &dyn CommandRunner) -> String {
+    let Some(display_name) = find_terminal() else {
+        return std::env::var("TERM").unwrap_or_else(|_| "Unknown".to_string());
+    };
+
+    // Try to get version
+    if let Some(version) = cmd.run(display_name, &["--version"])
+        && let Some(line) = version.lines().next()
+    {
+        if display_name == "ghostty" {
+            return format!("ghostty {}", line.split_whitespace().last().unwrap_or(""));
+        }
+
+        // Extract version from line
+        for part in line.split_whitespace() {
+            if part
+                .chars()
+                .next()
+                .map(|c| c.is_ascii_digit())
+                .unwrap_or(false)
+                || part.starts_with('v')
+            {
+                return format!("{} {}", display_name, part.trim_start_matches('v'));
+            }
+        }
+    }
+
+    display_name.to_string()
 }
 
 pub fn get_terminal_font() -> Option<String> {
@@ -267,11 +364,10 @@ pub fn get_terminal_font() -> Option<String> {
     None
 }
 
-pub fn get_multiplexer() -> Option<String> {
+pub fn get_multiplexer(cmd: &dyn CommandRunner) -> Option<String> {
     // Check TMUX
     if std::env::var("TMUX").is_ok() {
-        if let Some(output) = run_cmd("tmux", &["-V"]) {
-            let version = String::from_utf8_lossy(&output.stdout);
+        if let Some(version) = cmd.run("tmux", &["-V"]) {
             let version = version.trim();
             if !version.is_empty() {
                 return Some(version.to_string());
@@ -282,8 +378,7 @@ pub fn get_multiplexer() -> Option<String> {
 
     // Check Zellij
     if std::env::var("ZELLIJ").is_ok() || std::env::var("ZELLIJ_SESSION_NAME").is_ok() {
-        if let Some(output) = run_cmd("zellij", &["--version"]) {
-            let version = String::from_utf8_lossy(&output.stdout);
+        if let Some(version) = cmd.run("zellij", &["--version"]) {
             let version = version.trim().replace("zellij ", "");
             if !version.is_empty() {
                 return Some(format!("Zellij {}", version));
@@ -300,177 +395,626 @@ pub fn get_multiplexer() -> Option<String> {
     None
 }
 
-pub fn get_monitors() -> Vec<MonitorInfo> {
-    let mut monitors = Vec::new();
+/// Connected monitors. `wm` is the `get_wm` description, used to pick the
+/// compositor to ask for the active modes; GNOME's saved layout is looked up
+/// under `home`.
+pub fn get_monitors(
+    root: &Sysroot,
+    cmd: &dyn CommandRunner,
+    wm: &str,
+    home: Option<&Path>,
+) -> Vec<MonitorInfo> {
+    // Connected outputs straight from the kernel, at their preferred mode
+    let (connected, edids) = read_drm_connectors(root);
+
+    // The active mode comes from the display server when one answers:
+    // kscreen-doctor on KDE Plasma (best source), the compositor's own IPC,
+    // else xrandr on X11
+    let mut active = Vec::new();
+    if let Some(raw) = cmd.run("kscreen-doctor", &["-o"]) {
+        active = parse_kscreen_outputs(&raw, &edids);
+    }
+    if active.is_empty() {
+        active = query_compositor(wm, cmd, home, &connected, &edids);
+    }
+    if active.is_empty()
+        && std::env::var("DISPLAY").is_ok()
+        && let Some(xrandr) = cmd.run("xrandr", &["--query"])
+    {
+        active = parse_xrandr_query(&xrandr, &edids);
+    }
 
-    // First try to get monitor names from EDID
-    let mut edid_names: std::collections::HashMap<String, String> =
-        std::collections::HashMap::new();
-
-    if let Ok(entries) = fs::read_dir("/sys/class/drm") {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            let dir_name = path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-
-            if dir_name.starts_with("card") && dir_name.contains('-') {
-                let edid_path = path.join("edid");
-                if edid_path.exists()
-                    && let Ok(edid_bytes) = fs::read(&edid_path)
-                    && edid_bytes.len() >= 128
-                {
-                    // Extract monitor name from EDID (descriptor blocks start at byte 54)
-                    if let Some(name) = extract_edid_name(&edid_bytes) {
-                        // Map card1-DP-2 -> DP-2
-                        let connector = dir_name.split('-').skip(1).collect::<Vec<_>>().join("-");
-                        edid_names.insert(connector, name);
-                    }
-                }
+    merge_active_modes(connected, active)
+}
+
+/// Connected, enabled outputs under `/sys/class/drm` at their preferred
+/// mode, plus every connector's decoded EDID keyed by connector name.
+fn read_drm_connectors(root: &Sysroot) -> (Vec<MonitorInfo>, HashMap<String, Edid>) {
+    let mut monitors = Vec::new();
+    let mut edids: HashMap<String, Edid> = HashMap::new();
+
+    let mut entries: Vec<_> = root
+        .read_dir("/sys/class/drm")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("card") && name.contains('-')
+        })
+        .collect();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let read = |file: &str| fs::read_to_string(path.join(file)).ok();
+        // Map card1-DP-2 -> DP-2
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        let connector = dir_name.split('-').skip(1).collect::<Vec<_>>().join("-");
+
+        if let Ok(edid_bytes) = fs::read(path.join("edid"))
+            && let Some(mut edid) = parse_edid(&edid_bytes)
+        {
+            if let Some(vendor) = lookup_pnp_id(root, &edid.vendor_id) {
+                edid.vendor = Some(vendor);
             }
+            edids.insert(connector.clone(), edid);
+        }
+
+        let connected = read("status").is_some_and(|s| s.trim() == "connected");
+        let enabled = read("enabled").is_none_or(|s| s.trim() == "enabled");
+        // The kernel lists the preferred mode first
+        let preferred = read("modes").and_then(|m| m.lines().next().map(str::to_string));
+        if connected
+            && enabled
+            && let Some(resolution) = preferred
+        {
+            let edid = edids.get(&connector);
+            let refresh_rate = edid
+                .and_then(|e| e.preferred_mode)
+                .filter(|m| format!("{}x{}", m.width, m.height) == resolution)
+                .map(|m| format!("{:.0} Hz", m.refresh_hz))
+                .unwrap_or_default();
+            monitors.push(MonitorInfo::new(
+                &connector,
+                resolution,
+                refresh_rate,
+                false,
+                edid,
+            ));
         }
     }
 
-    // Use kscreen-doctor for KDE Plasma (best source)
-    if let Some(output) = run_cmd("kscreen-doctor", &["-o"]) {
-        // Strip ANSI color codes
-        let raw = String::from_utf8_lossy(&output.stdout);
-        let kscreen = strip_ansi_codes(&raw);
-        let mut current_output = String::new();
-        let mut current_res = String::new();
-        let mut current_rate = String::new();
-        let mut has_hdr = false;
-
-        for line in kscreen.lines() {
-            let line = line.trim();
-
-            if line.starts_with("Output:") {
-                // Save previous if exists
-                if !current_output.is_empty() && !current_res.is_empty() {
-                    let name = edid_names
-                        .get(&current_output)
-                        .cloned()
-                        .unwrap_or(current_output.clone());
-                    monitors.push(MonitorInfo {
-                        name,
-                        resolution: current_res.clone(),
-                        refresh_rate: current_rate.clone(),
-                        hdr: has_hdr,
-                    });
-                }
+    (monitors, edids)
+}
 
-                // Parse: "Output: 1 DP-2 uuid..."
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 3 {
-                    current_output = parts[2].to_string();
-                }
-                current_res.clear();
-                current_rate.clear();
-                has_hdr = false;
-            } else if line.starts_with("Modes:") {
-                // Find the mode with * (current)
-                // Format: 2:3840x2160@240.02*
-                for part in line.split_whitespace() {
-                    if part.contains('*') {
-                        if let Some(mode) = part.split(':').nth(1) {
-                            let mode = mode.trim_end_matches('*');
-                            if let Some((res, rate)) = mode.split_once('@') {
-                                current_res = res.to_string();
-                                // Round refresh rate (remove trailing *)
-                                let rate_clean = rate.trim_end_matches('*');
-                                if let Ok(rate_f) = rate_clean.parse::<f64>() {
-                                    current_rate = format!("{:.0} Hz", rate_f);
-                                }
-                            }
-                        }
-                        break;
+/// Replace the DRM view of each output with the display server's, which
+/// knows the active mode. Active outputs with no DRM connector (nested or
+/// VNC sessions, virtual outputs, NVIDIA without KMS) are appended. X drivers
+/// may name outputs differently from DRM (`DisplayPort-0` vs `DP-1`); if no
+/// names line up, the display server's list wins outright.
+fn merge_active_modes(connected: Vec<MonitorInfo>, active: Vec<MonitorInfo>) -> Vec<MonitorInfo> {
+    let names_match = active
+        .iter()
+        .any(|a| connected.iter().any(|c| c.connector == a.connector));
+    if !names_match {
+        return if active.is_empty() { connected } else { active };
+    }
+
+    let mut merged: Vec<MonitorInfo> = connected
+        .into_iter()
+        .map(|monitor| {
+            active
+                .iter()
+                .find(|a| a.connector == monitor.connector)
+                .cloned()
+                .unwrap_or(monitor)
+        })
+        .collect();
+    let unmatched: Vec<MonitorInfo> = active
+        .into_iter()
+        .filter(|a| !merged.iter().any(|m| m.connector == a.connector))
+        .collect();
+    merged.extend(unmatched);
+    merged
+}
+
+/// Parse `kscreen-doctor -o`, filling outputs in from their EDID where known.
+fn parse_kscreen_outputs(raw: &str, edids: &HashMap<String, Edid>) -> Vec<MonitorInfo> {
+    // Strip ANSI color codes
+    let kscreen = strip_ansi_codes(raw);
+    let mut monitors = Vec::new();
+
+    // Each output is an "Output: 1 DP-2 ..." line followed by its properties
+    for block in kscreen.split("Output:").skip(1) {
+        let Some(connector) = block.split_whitespace().nth(1) else {
+            continue;
+        };
+        let mut resolution = String::new();
+        let mut refresh_rate = String::new();
+        let mut hdr = false;
+        let mut scale = None;
+        let mut transform = None;
+        let mut vrr = None;
+
+        for line in block.lines().map(str::trim) {
+            if let Some(modes) = line.strip_prefix("Modes:") {
+                // The current mode is marked with * (or *! when it is also
+                // the preferred mode): 2:3840x2160@240.02*!
+                if let Some(mode) = modes.split_whitespace().find(|m| m.contains('*'))
+                    && let Some(mode) = mode.split(':').nth(1)
+                    && let Some((res, rate)) = mode.trim_end_matches(['*', '!']).split_once('@')
+                {
+                    resolution = res.to_string();
+                    if let Ok(rate) = rate.parse::<f64>() {
+                        refresh_rate = format!("{:.0} Hz", rate);
                     }
                 }
+            } else if let Some(value) = line.strip_prefix("Scale:") {
+                scale = value.trim().parse().ok();
+            } else if let Some(value) = line.strip_prefix("Rotation:") {
+                // KScreen::Output::Rotation flags
+                transform = match value.trim() {
+                    "1" => Some("normal"),
+                    "2" => Some("90"),
+                    "4" => Some("180"),
+                    "8" => Some("270"),
+                    _ => None,
+                }
+                .map(str::to_string);
+            } else if let Some(value) = line.strip_prefix("Vrr:") {
+                vrr = match value.trim() {
+                    "Automatic" | "Always" => Some(true),
+                    "Never" | "incapable" => Some(false),
+                    _ => None,
+                };
             } else if line.contains("HDR:") && line.contains("enabled") {
-                has_hdr = true;
+                hdr = true;
             }
         }
 
-        // Don't forget the last one
-        if !current_output.is_empty() && !current_res.is_empty() {
-            let name = edid_names
-                .get(&current_output)
-                .cloned()
-                .unwrap_or(current_output.clone());
-            monitors.push(MonitorInfo {
-                name,
-                resolution: current_res,
-                refresh_rate: current_rate,
-                hdr: has_hdr,
-            });
+        if !resolution.is_empty() {
+            let mut monitor = MonitorInfo::new(
+                connector,
+                resolution,
+                refresh_rate,
+                hdr,
+                edids.get(connector),
+            );
+            monitor.scale = scale;
+            monitor.transform = transform;
+            monitor.vrr = vrr;
+            monitors.push(monitor);
         }
     }
 
-    // Fallback to xrandr if kscreen-doctor didn't work
-    if monitors.is_empty()
-        && std::env::var("DISPLAY").is_ok()
-        && let Some(output) = run_cmd("xrandr", &["--query"])
+    monitors
+}
+
+/// Active outputs from the running compositor's IPC, picked from the
+/// `get_wm` description ("Hyprland (Wayland)", "Mutter (Wayland)", ...).
+fn query_compositor(
+    wm: &str,
+    cmd: &dyn CommandRunner,
+    home: Option<&Path>,
+    connected: &[MonitorInfo],
+    edids: &HashMap<String, Edid>,
+) -> Vec<MonitorInfo> {
+    let wm = wm.to_lowercase();
+    if wm.contains("hyprland") {
+        return cmd
+            .run("hyprctl", &["monitors", "-j"])
+            .map(|json| parse_hyprctl_monitors(&json, edids))
+            .unwrap_or_default();
+    }
+    if wm.contains("mutter") || wm.contains("gnome") {
+        let connectors: Vec<&str> = connected.iter().map(|m| m.connector.as_str()).collect();
+        return home
+            .and_then(|home| fs::read_to_string(home.join(".config/monitors.xml")).ok())
+            .map(|xml| parse_gnome_monitors_xml(&xml, &connectors, edids))
+            .unwrap_or_default();
+    }
+    if wm.contains("sway")
+        && let Some(json) = cmd.run("swaymsg", &["-t", "get_outputs", "-r"])
     {
-        let xrandr = String::from_utf8_lossy(&output.stdout);
-        let mut current_output = String::new();
-
-        for line in xrandr.lines() {
-            if line.contains(" connected") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if !parts.is_empty() {
-                    current_output = parts[0].to_string();
-                }
-            } else if line.contains('*') && !current_output.is_empty() {
-                // Current resolution line
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    let res = parts[0];
-                    let rate = parts[1].trim_end_matches('*').trim_end_matches('+');
-
-                    let name = edid_names
-                        .get(&current_output)
-                        .cloned()
-                        .unwrap_or(current_output.clone());
-
-                    monitors.push(MonitorInfo {
-                        name,
-                        resolution: res.to_string(),
-                        refresh_rate: format!("{} Hz", rate),
-                        hdr: false,
-                    });
-                    current_output.clear();
-                }
-            }
-        }
+        return parse_sway_outputs(&json, edids);
+    }
+    // Other wlroots compositors (river, labwc, wayfire, ...)
+    if wm.contains("wayland")
+        && let Some(json) = cmd.run("wlr-randr", &["--json"])
+    {
+        return parse_wlr_randr(&json, edids);
     }
+    Vec::new()
+}
+
+/// Wayland `wl_output` transform names, indexed by their enum value.
+const TRANSFORMS: [&str; 8] = [
+    "normal",
+    "90",
+    "180",
+    "270",
+    "flipped",
+    "flipped-90",
+    "flipped-180",
+    "flipped-270",
+];
+
+/// Parse `hyprctl monitors -j`.
+fn parse_hyprctl_monitors(json: &str, edids: &HashMap<String, Edid>) -> Vec<MonitorInfo> {
+    let Ok(serde_json::Value::Array(outputs)) = serde_json::from_str(json) else {
+        return Vec::new();
+    };
+    outputs
+        .iter()
+        .filter(|o| !o["disabled"].as_bool().unwrap_or(false))
+        .filter_map(|o| {
+            let connector = o["name"].as_str()?;
+            let resolution = format!("{}x{}", o["width"].as_u64()?, o["height"].as_u64()?);
+            let refresh_rate = o["refreshRate"]
+                .as_f64()
+                .map(|hz| format!("{:.0} Hz", hz))
+                .unwrap_or_default();
+            let mut monitor = MonitorInfo::new(
+                connector,
+                resolution,
+                refresh_rate,
+                false,
+                edids.get(connector),
+            );
+            monitor.scale = o["scale"].as_f64();
+            monitor.transform = o["transform"]
+                .as_u64()
+                .and_then(|t| TRANSFORMS.get(t as usize))
+                .map(|t| t.to_string());
+            monitor.vrr = o["vrr"].as_bool();
+            Some(monitor)
+        })
+        .collect()
+}
 
+/// Parse `swaymsg -t get_outputs -r`. Refresh rates are in mHz.
+fn parse_sway_outputs(json: &str, edids: &HashMap<String, Edid>) -> Vec<MonitorInfo> {
+    let Ok(serde_json::Value::Array(outputs)) = serde_json::from_str(json) else {
+        return Vec::new();
+    };
+    outputs
+        .iter()
+        .filter(|o| o["active"].as_bool().unwrap_or(false))
+        .filter_map(|o| {
+            let connector = o["name"].as_str()?;
+            let mode = &o["current_mode"];
+            let resolution = format!("{}x{}", mode["width"].as_u64()?, mode["height"].as_u64()?);
+            let refresh_rate = mode["refresh"]
+                .as_f64()
+                .map(|mhz| format!("{:.0} Hz", mhz / 1000.0))
+                .unwrap_or_default();
+            let mut monitor = MonitorInfo::new(
+                connector,
+                resolution,
+                refresh_rate,
+                o["hdr"].as_bool().unwrap_or(false),
+                edids.get(connector),
+            );
+            monitor.scale = o["scale"].as_f64();
+            monitor.transform = o["transform"].as_str().map(str::to_string);
+            monitor.vrr = o["adaptive_sync_status"].as_str().map(|s| s == "enabled");
+            Some(monitor)
+        })
+        .collect()
+}
+
+/// Parse `wlr-randr --json`.
+fn parse_wlr_randr(json: &str, edids: &HashMap<String, Edid>) -> Vec<MonitorInfo> {
+    let Ok(serde_json::Value::Array(outputs)) = serde_json::from_str(json) else {
+        return Vec::new();
+    };
+    outputs
+        .iter()
+        .filter(|o| o["enabled"].as_bool().unwrap_or(false))
+        .filter_map(|o| {
+            let connector = o["name"].as_str()?;
+            let mode = o["modes"]
+                .as_array()?
+                .iter()
+                .find(|m| m["current"].as_bool().unwrap_or(false))?;
+            let resolution = format!("{}x{}", mode["width"].as_u64()?, mode["height"].as_u64()?);
+            let refresh_rate = mode["refresh"]
+                .as_f64()
+                .map(|hz| format!("{:.0} Hz", hz))
+                .unwrap_or_default();
+            let mut monitor = MonitorInfo::new(
+                connector,
+                resolution,
+                refresh_rate,
+                false,
+                edids.get(connector),
+            );
+            monitor.scale = o["scale"].as_f64();
+            monitor.transform = o["transform"].as_str().map(str::to_string);
+            monitor.vrr = o["adaptive_sync"].as_bool();
+            Some(monitor)
+        })
+        .collect()
+}
+
+/// Parse GNOME's `~/.config/monitors.xml`, using the configuration whose
+/// monitors are the ones connected now. Mutter keeps one configuration per
+/// set of monitors it has seen.
+fn parse_gnome_monitors_xml(
+    xml: &str,
+    connected: &[&str],
+    edids: &HashMap<String, Edid>,
+) -> Vec<MonitorInfo> {
+    let configurations = xml_elements(xml, "configuration");
+    let connectors = |config: &str| -> Vec<String> {
+        let mut connectors: Vec<String> = xml_elements(config, "logicalmonitor")
+            .into_iter()
+            .flat_map(|lm| xml_elements(lm, "monitor"))
+            .filter_map(|m| xml_text(m, "connector").map(str::to_string))
+            .collect();
+        connectors.sort();
+        connectors
+    };
+    let mut wanted: Vec<String> = connected.iter().map(|c| c.to_string()).collect();
+    wanted.sort();
+
+    let config = configurations
+        .iter()
+        .find(|config| connectors(config) == wanted)
+        .or_else(|| configurations.first().filter(|_| connected.is_empty()));
+    let Some(config) = config else {
+        return Vec::new();
+    };
+
+    let mut monitors = Vec::new();
+    for logical in xml_elements(config, "logicalmonitor") {
+        let scale = xml_text(logical, "scale").and_then(|s| s.parse().ok());
+        let rotation = match xml_text(logical, "rotation").unwrap_or("normal") {
+            "left" => "90",
+            "upside_down" => "180",
+            "right" => "270",
+            _ => "normal",
+        };
+        let flipped = xml_text(logical, "flipped") == Some("yes");
+        let transform = match (flipped, rotation) {
+            (false, rotation) => rotation.to_string(),
+            (true, "normal") => "flipped".to_string(),
+            (true, rotation) => format!("flipped-{}", rotation),
+        };
+
+        for monitor in xml_elements(logical, "monitor") {
+            let Some(connector) = xml_text(monitor, "connector") else {
+                continue;
+            };
+            let mode = xml_text(monitor, "mode").unwrap_or_default();
+            let (Some(width), Some(height)) = (xml_text(mode, "width"), xml_text(mode, "height"))
+            else {
+                continue;
+            };
+            let refresh_rate = xml_text(mode, "rate")
+                .and_then(|r| r.parse::<f64>().ok())
+                .map(|hz| format!("{:.0} Hz", hz))
+                .unwrap_or_default();
+            let mut info = MonitorInfo::new(
+                connector,
+                format!("{}x{}", width, height),
+                refresh_rate,
+                false,
+                edids.get(connector),
+            );
+            info.scale = scale;
+            info.transform = Some(transform.clone());
+            monitors.push(info);
+        }
+    }
     monitors
 }
 
-fn extract_edid_name(edid: &[u8]) -> Option<String> {
-    // EDID descriptor blocks are at bytes 54-125 (4 blocks of 18 bytes each)
-    for i in 0..4 {
-        let offset = 54 + i * 18;
-        if offset + 18 > edid.len() {
+/// Contents of each `<tag>...</tag>` element, in order. Enough for
+/// monitors.xml, whose elements carry no attributes and never nest inside
+/// one of the same name.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        let Some(end) = after.find(&close) else {
             break;
-        }
+        };
+        elements.push(&after[..end]);
+        rest = &after[end + close.len()..];
+    }
+    elements
+}
 
-        // Monitor name descriptor has tag 0xFC
-        if edid[offset] == 0 && edid[offset + 1] == 0 && edid[offset + 3] == 0xFC {
-            let name_bytes = &edid[offset + 5..offset + 18];
-            let name: String = name_bytes
-                .iter()
-                .take_while(|&&b| b != 0x0A && b != 0x00)
-                .filter(|&&b| (0x20..0x7F).contains(&b))
-                .map(|&b| b as char)
-                .collect();
-            let name = name.trim().to_string();
-            if !name.is_empty() {
-                return Some(name);
+/// Trimmed text of the first `<tag>` element.
+fn xml_text<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    xml_elements(xml, tag).first().map(|text| text.trim())
+}
+
+/// Parse `xrandr --query`, taking the active mode of each connected output.
+fn parse_xrandr_query(xrandr: &str, edids: &HashMap<String, Edid>) -> Vec<MonitorInfo> {
+    let mut monitors = Vec::new();
+    let mut current_output = String::new();
+
+    for line in xrandr.lines() {
+        if line.contains(" connected") {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if !parts.is_empty() {
+                current_output = parts[0].to_string();
+            }
+        } else if line.contains('*') && !current_output.is_empty() {
+            // Current resolution line
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 2 {
+                let res = parts[0];
+                let rate = parts[1].trim_end_matches(['*', '+']);
+
+                monitors.push(MonitorInfo::new(
+                    &current_output,
+                    res.to_string(),
+                    format!("{} Hz", rate),
+                    false,
+                    edids.get(&current_output),
+                ));
+                current_output.clear();
             }
         }
     }
-    None
+
+    monitors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+
+    #[test]
+    fn test_get_monitors_from_kscreen() {
+        let machine = "arch-desktop";
+        let monitors = get_monitors(
+            &fixtures::root(machine),
+            &fixtures::commands(machine),
+            "KWin (Wayland)",
+            None,
+        );
+        let lines: Vec<String> = monitors.iter().map(|m| m.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "(PG32UCDM) 3840x2160 @ 240 Hz (x1.5) 31.5\" [HDR]",
+                "(DELL S2721DGF) 2560x1440 @ 144 Hz 27\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_monitors_from_drm() {
+        // No display server to ask: preferred modes from sysfs, and the
+        // connected but disabled DP-1 is skipped
+        let machine = "nixos-framework";
+        let monitors = get_monitors(
+            &fixtures::root(machine),
+            &fixtures::commands(machine),
+            "Unknown (TTY)",
+            None,
+        );
+        let lines: Vec<String> = monitors.iter().map(|m| m.to_string()).collect();
+        assert_eq!(lines, ["(eDP-1) 2256x1504 @ 60 Hz 13.5\""]);
+        assert_eq!(monitors[0].vendor.as_deref(), Some("BOE"));
+    }
+
+    #[test]
+    fn test_get_monitors_from_compositors() {
+        let lines = |machine: &str, wm: &str| -> Vec<String> {
+            let home = fixtures::home(machine);
+            get_monitors(
+                &fixtures::root(machine),
+                &fixtures::commands(machine),
+                wm,
+                Some(&home),
+            )
+            .iter()
+            .map(|m| m.to_string())
+            .collect()
+        };
+
+        assert_eq!(
+            lines("nixos-framework", "Hyprland (Wayland)"),
+            ["(eDP-1) 2256x1504 @ 60 Hz (x1.5) 13.5\""]
+        );
+        assert_eq!(
+            lines("void-laptop", "Sway (Wayland)"),
+            ["(eDP-1) 1920x1200 @ 60 Hz (x1.25)"]
+        );
+        assert_eq!(
+            lines("gentoo-workstation", "river (Wayland)"),
+            [
+                "(DP-1) 3840x2160 @ 60 Hz (x2)",
+                "(DP-2) 3840x2160 @ 60 Hz (x2)"
+            ]
+        );
+        // The undocked layout is the one matching the connected outputs
+        assert_eq!(
+            lines("ubuntu-thinkpad", "Mutter (Wayland)"),
+            ["(eDP-1) 2880x1800 @ 120 Hz (x1.75) 14\""]
+        );
+    }
+
+    #[test]
+    fn test_parse_gnome_monitors_xml_transform() {
+        let xml =
+            fs::read_to_string(fixtures::home("ubuntu-thinkpad").join(".config/monitors.xml"))
+                .unwrap();
+        let monitors = parse_gnome_monitors_xml(&xml, &["eDP-1", "HDMI-A-1"], &HashMap::new());
+        let transforms: Vec<_> = monitors.iter().map(|m| m.transform.as_deref()).collect();
+        assert_eq!(transforms, [Some("normal"), Some("90")]);
+        assert_eq!(monitors[1].to_string(), "(HDMI-A-1) 3840x2160 @ 60 Hz");
+    }
+
+    #[test]
+    fn test_parse_kscreen_transform_and_vrr() {
+        let raw = fixtures::commands("arch-desktop")
+            .run("kscreen-doctor", &["-o"])
+            .unwrap();
+        let monitors = parse_kscreen_outputs(&raw, &HashMap::new());
+        assert_eq!(monitors[0].scale, Some(1.5));
+        assert_eq!(monitors[0].transform.as_deref(), Some("normal"));
+        assert_eq!(monitors[0].vrr, Some(true));
+        assert_eq!(monitors[1].vrr, Some(false));
+    }
+
+    #[test]
+    fn test_merge_active_modes() {
+        let monitor = |connector: &str, rate: &str| {
+            MonitorInfo::new(
+                connector,
+                "1920x1080".to_string(),
+                rate.to_string(),
+                false,
+                None,
+            )
+        };
+        let connected = vec![monitor("DP-1", "60 Hz"), monitor("HDMI-A-1", "60 Hz")];
+
+        let merged = merge_active_modes(connected.clone(), vec![monitor("HDMI-A-1", "75 Hz")]);
+        let rates: Vec<&str> = merged.iter().map(|m| m.refresh_rate.as_str()).collect();
+        assert_eq!(rates, ["60 Hz", "75 Hz"]);
+
+        // A virtual output the compositor drives without a DRM connector
+        let merged = merge_active_modes(
+            connected.clone(),
+            vec![monitor("HDMI-A-1", "75 Hz"), monitor("HEADLESS-1", "60 Hz")],
+        );
+        let connectors: Vec<&str> = merged.iter().map(|m| m.connector.as_str()).collect();
+        assert_eq!(connectors, ["DP-1", "HDMI-A-1", "HEADLESS-1"]);
+
+        // X driver names that don't match DRM's
+        let merged = merge_active_modes(connected, vec![monitor("DisplayPort-0", "144 Hz")]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].connector, "DisplayPort-0");
+    }
+
+    #[test]
+    fn test_parse_xrandr_query() {
+        let xrandr = fixtures::commands("ubuntu-thinkpad")
+            .run("xrandr", &["--query"])
+            .unwrap();
+        let edid = Edid {
+            name: Some("LEN40B2".to_string()),
+            size_mm: Some((302, 189)),
+            ..Default::default()
+        };
+        let edids = HashMap::from([("eDP-1".to_string(), edid)]);
+        let monitors = parse_xrandr_query(&xrandr, &edids);
+        assert_eq!(monitors.len(), 1);
+        assert_eq!(
+            monitors[0].to_string(),
+            "(LEN40B2) 2880x1800 @ 120.00 Hz 14\""
+        );
+        assert_eq!(monitors[0].dpi, Some(242));
+    }
 }
@@ -0,0 +1,332 @@
+//! Physical block devices from `/sys/block`, with their partitions and where
+//! they are mounted.
+
+use super::sysroot::Sysroot;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Sizes in `/sys/block/*/size` are always in 512-byte sectors, whatever
+/// the device's logical block size.
+const SECTOR_BYTES: u64 = 512;
+
+/// One partition and the mount points using it, directly or through a
+/// device-mapper volume (LUKS, LVM) stacked on top.
+#[derive(Clone, Debug, Serialize)]
+pub struct PartitionInfo {
+    /// Kernel name, e.g. `nvme0n1p2`.
+    pub name: String,
+    pub size_bytes: u64,
+    pub mounts: Vec<String>,
+}
+
+/// One physical drive.
+#[derive(Clone, Debug, Serialize)]
+pub struct DriveInfo {
+    /// Kernel name, e.g. `nvme0n1` or `sda`.
+    pub name: String,
+    pub model: Option<String>,
+    pub size_bytes: u64,
+    pub rotational: bool,
+    pub removable: bool,
+    /// `NVMe`, `SATA`, `USB`, `MMC`, `virtio` or `Xen`.
+    pub transport: Option<String>,
+    pub partitions: Vec<PartitionInfo>,
+    /// Mounts of the whole device, for drives formatted without a
+    /// partition table.
+    pub mounts: Vec<String>,
+}
+
+impl DriveInfo {
+    /// Paravirtualized disks, whose rotational flag says nothing about the
+    /// storage behind them.
+    pub fn is_virtual(&self) -> bool {
+        matches!(self.transport.as_deref(), Some("virtio" | "Xen"))
+    }
+
+    /// Every mount point on the drive, partitions first.
+    pub fn all_mounts(&self) -> Vec<&str> {
+        self.partitions
+            .iter()
+            .flat_map(|part| &part.mounts)
+            .chain(&self.mounts)
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+impl fmt::Display for DriveInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.model {
+            Some(ref model) => write!(f, "{}: {} ", self.name, model)?,
+            None => write!(f, "{} ", self.name)?,
+        }
+        let kind = if self.is_virtual() {
+            "virtual"
+        } else if self.rotational {
+            "HDD"
+        } else {
+            "SSD"
+        };
+        write!(f, "({}, {}", format_size(self.size_bytes), kind)?;
+        if let Some(ref transport) = self.transport {
+            write!(f, ", {}", transport)?;
+        }
+        if self.removable {
+            write!(f, ", removable")?;
+        }
+        write!(f, ")")?;
+        let mounts = self.all_mounts();
+        if !mounts.is_empty() {
+            write!(f, " - {}", mounts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    let gib = bytes as f64 / GIB;
+    if gib >= 1024.0 {
+        format!("{:.2} TiB", gib / 1024.0)
+    } else {
+        format!("{:.2} GiB", gib)
+    }
+}
+
+/// Physical drives in name order. Loop, RAM, zram, device-mapper and md
+/// devices live under `/sys/devices/virtual` and are left out, as are empty
+/// card readers reporting a size of zero.
+pub fn get_drives(root: &Sysroot) -> Vec<DriveInfo> {
+    let Ok(entries) = root.read_dir("/sys/block") else {
+        return Vec::new();
+    };
+    let mounts = read_mounts(root);
+
+    let mut drives: Vec<DriveInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let target = fs::read_link(&dir).ok()?.to_string_lossy().into_owned();
+            if target.contains("/virtual/") {
+                return None;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let size_bytes = read_number(&dir, "size")? * SECTOR_BYTES;
+            if size_bytes == 0 {
+                return None;
+            }
+            Some(DriveInfo {
+                model: read_value(&dir, "device/model").or_else(|| read_value(&dir, "device/name")),
+                size_bytes,
+                rotational: read_number(&dir, "queue/rotational") == Some(1),
+                removable: read_number(&dir, "removable") == Some(1),
+                transport: transport(&target).map(str::to_string),
+                partitions: read_partitions(root, &dir, &mounts),
+                mounts: device_mounts(root, &dir, &name, &mounts),
+                name,
+            })
+        })
+        .collect();
+    drives.sort_by(|a, b| a.name.cmp(&b.name));
+    drives
+}
+
+/// The bus, from the `/sys/block` symlink into the device tree. USB is
+/// checked first since USB-SATA bridges also show an `ata` path on some
+/// controllers.
+fn transport(target: &str) -> Option<&'static str> {
+    if target.contains("/nvme/") {
+        Some("NVMe")
+    } else if target.contains("/usb") {
+        Some("USB")
+    } else if target.contains("/ata") {
+        Some("SATA")
+    } else if target.contains("/mmc_host/") {
+        Some("MMC")
+    } else if target.contains("/virtio") {
+        Some("virtio")
+    } else if target.contains("/xen/") || target.contains("/vbd-") {
+        Some("Xen")
+    } else {
+        None
+    }
+}
+
+fn read_partitions(root: &Sysroot, dir: &Path, mounts: &[(String, String)]) -> Vec<PartitionInfo> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut partitions: Vec<(u32, PartitionInfo)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let part_dir = entry.path();
+            let number = read_number(&part_dir, "partition")? as u32;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            Some((
+                number,
+                PartitionInfo {
+                    size_bytes: read_number(&part_dir, "size").unwrap_or(0) * SECTOR_BYTES,
+                    mounts: device_mounts(root, &part_dir, &name, mounts),
+                    name,
+                },
+            ))
+        })
+        .collect();
+    partitions.sort_by_key(|(number, _)| *number);
+    partitions.into_iter().map(|(_, part)| part).collect()
+}
+
+/// Mount points of the device `name`, or of any device-mapper volume stacked
+/// on it, however deep: LVM on LUKS puts the mounted volume two `holders`
+/// levels above the partition.
+fn device_mounts(
+    root: &Sysroot,
+    dir: &Path,
+    name: &str,
+    mounts: &[(String, String)],
+) -> Vec<String> {
+    let mut sources = vec![format!("/dev/{}", name)];
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut pending: Vec<PathBuf> = vec![dir.join("holders")];
+    while let Some(holders) = pending.pop() {
+        let Ok(entries) = fs::read_dir(holders) else {
+            continue;
+        };
+        for holder in entries.flatten() {
+            let holder = holder.file_name().to_string_lossy().into_owned();
+            if !visited.insert(holder.clone()) {
+                continue;
+            }
+            if let Some(dm_name) = root.read_trimmed(format!("/sys/block/{}/dm/name", holder)) {
+                sources.push(format!("/dev/mapper/{}", dm_name));
+            }
+            sources.push(format!("/dev/{}", holder));
+            pending.push(root.path(format!("/sys/block/{}/holders", holder)));
+        }
+    }
+
+    let mut found: Vec<String> = Vec::new();
+    for (source, mount) in mounts {
+        if sources.contains(source) && !found.contains(mount) {
+            found.push(mount.clone());
+        }
+    }
+    found
+}
+
+/// `(source, mount point)` pairs from `/proc/mounts`.
+fn read_mounts(root: &Sysroot) -> Vec<(String, String)> {
+    let Ok(content) = root.read_to_string("/proc/mounts") else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?;
+            let mount = fields.next()?;
+            Some((unescape_mount(source), unescape_mount(mount)))
+        })
+        .collect()
+}
+
+/// `/proc/mounts` writes spaces, tabs, newlines and backslashes as octal
+/// escapes (`\040`).
+fn unescape_mount(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(pos) = rest.find('\\') {
+        out.push_str(&rest[..pos]);
+        let escape = rest.get(pos + 1..pos + 4);
+        match escape.and_then(|octal| u8::from_str_radix(octal, 8).ok()) {
+            Some(byte) => {
+                out.push(byte as char);
+                rest = &rest[pos + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[pos + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn read_value(dir: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(dir.join(file))
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn read_number(dir: &Path, file: &str) -> Option<u64> {
+    read_value(dir, file)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+
+    #[test]
+    fn test_drives_from_sys_block() {
+        let drives = get_drives(&fixtures::root("arch-desktop"));
+        let names: Vec<&str> = drives.iter().map(|d| d.name.as_str()).collect();
+        // loop0 and zram0 are virtual
+        assert_eq!(names, ["nvme0n1", "sda", "sdb"]);
+
+        assert_eq!(
+            drives[0].to_string(),
+            "nvme0n1: Samsung SSD 990 PRO 2TB (1.82 TiB, SSD, NVMe) - /boot, /, /home"
+        );
+        assert_eq!(drives[0].partitions.len(), 2);
+        assert_eq!(drives[0].partitions[1].name, "nvme0n1p2");
+
+        assert_eq!(
+            drives[1].to_string(),
+            "sda: WDC WD40EFRX-68N (3.64 TiB, HDD, SATA) - /srv/media"
+        );
+        assert_eq!(
+            drives[2].to_string(),
+            "sdb: Ultra Fit (57.30 GiB, SSD, USB, removable) - /run/media/alex/USB STICK"
+        );
+    }
+
+    #[test]
+    fn test_drives_through_device_mapper() {
+        // LVM on LUKS on the second partition: nvme0n1p2 -> dm-0
+        // (nvme0n1p2_crypt) -> dm-1 (vgubuntu-root)
+        let drives = get_drives(&fixtures::root("ubuntu-thinkpad"));
+        assert_eq!(drives.len(), 1);
+        assert_eq!(drives[0].partitions[1].mounts, ["/", "/home"]);
+        assert_eq!(drives[0].transport.as_deref(), Some("NVMe"));
+
+        assert!(get_drives(&fixtures::root("alpine-docker")).is_empty());
+    }
+
+    #[test]
+    fn test_virtual_drive_without_model() {
+        // The empty CD-ROM drive reports a size of zero
+        let drives = get_drives(&fixtures::root("fedora-vm"));
+        assert_eq!(drives.len(), 1);
+        assert!(drives[0].rotational);
+        assert_eq!(
+            drives[0].to_string(),
+            "vda (256.00 GiB, virtual, virtio) - /boot/efi, /boot, /, /home"
+        );
+    }
+
+    #[test]
+    fn test_unescape_mount() {
+        assert_eq!(
+            unescape_mount("/run/media/USB\\040STICK"),
+            "/run/media/USB STICK"
+        );
+        assert_eq!(unescape_mount("/plain"), "/plain");
+        assert_eq!(unescape_mount("/odd\\x"), "/odd\\x");
+    }
+}
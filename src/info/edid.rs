@@ -0,0 +1,444 @@
+//! EDID decoding: the 128-byte base block plus any CTA-861 extension blocks,
+//! as exposed in `/sys/class/drm/*/edid`.
+
+use super::sysroot::Sysroot;
+use serde::Serialize;
+use std::fmt;
+
+const HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+
+/// Well-known PNP IDs, used when the system's `pnp.ids` isn't installed.
+const PNP_VENDORS: &[(&str, &str)] = &[
+    ("ACR", "Acer"),
+    ("AOC", "AOC"),
+    ("APP", "Apple"),
+    ("AUO", "AU Optronics"),
+    ("AUS", "ASUS"),
+    ("BNQ", "BenQ"),
+    ("BOE", "BOE"),
+    ("CMN", "Innolux"),
+    ("CSO", "CSOT"),
+    ("DEL", "Dell"),
+    ("ENC", "EIZO"),
+    ("GBT", "Gigabyte"),
+    ("GSM", "LG"),
+    ("HPN", "HP"),
+    ("HWP", "HP"),
+    ("IVM", "iiyama"),
+    ("IVO", "InfoVision"),
+    ("LEN", "Lenovo"),
+    ("LGD", "LG Display"),
+    ("MSI", "MSI"),
+    ("NEC", "NEC"),
+    ("PHL", "Philips"),
+    ("SAM", "Samsung"),
+    ("SDC", "Samsung Display"),
+    ("SHP", "Sharp"),
+    ("SNY", "Sony"),
+    ("VSC", "ViewSonic"),
+    ("XMI", "Xiaomi"),
+];
+
+/// Everything ghostfetch reads out of an EDID.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Edid {
+    /// Three-letter PNP manufacturer ID, e.g. `AUS`.
+    pub vendor_id: String,
+    /// Manufacturer name for `vendor_id`, if known.
+    pub vendor: Option<String>,
+    pub product_code: u16,
+    /// Monitor name descriptor (0xFC).
+    pub name: Option<String>,
+    /// Serial string descriptor (0xFF), else the numeric serial.
+    pub serial: Option<String>,
+    /// Year of manufacture, or the model year.
+    pub year: Option<u16>,
+    /// Physical image size, from the preferred timing when it gives one
+    /// (millimetre precision), else the base block's centimetres.
+    pub size_mm: Option<(u32, u32)>,
+    /// The preferred (native) timing.
+    pub preferred_mode: Option<Mode>,
+    pub hdr: Option<HdrMetadata>,
+    pub vrr: Option<VrrRange>,
+    /// Extended colorimetry from the CTA colorimetry data block.
+    pub colorimetry: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_hz: f64,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} @ {:.0} Hz",
+            self.width, self.height, self.refresh_hz
+        )
+    }
+}
+
+/// CTA-861 HDR static metadata. Luminances are in cd/m².
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct HdrMetadata {
+    /// Supported transfer functions, e.g. `SDR`, `PQ`, `HLG`.
+    pub eotfs: Vec<String>,
+    pub max_luminance: Option<f64>,
+    pub max_frame_avg_luminance: Option<f64>,
+    pub min_luminance: Option<f64>,
+}
+
+/// Variable refresh rate range.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct VrrRange {
+    pub min_hz: u32,
+    pub max_hz: u32,
+}
+
+impl fmt::Display for VrrRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{} Hz", self.min_hz, self.max_hz)
+    }
+}
+
+/// Decode an EDID blob. Returns `None` if it doesn't start with a valid base
+/// block; unknown or truncated extension blocks are skipped.
+pub fn parse_edid(bytes: &[u8]) -> Option<Edid> {
+    if bytes.len() < 128 || bytes[..8] != HEADER {
+        return None;
+    }
+
+    let mut edid = Edid::default();
+
+    // Manufacturer ID: three 5-bit letters, big-endian, 'A' = 1
+    let id = u16::from_be_bytes([bytes[8], bytes[9]]);
+    edid.vendor_id = [10, 5, 0]
+        .iter()
+        .map(|shift| (b'A' - 1 + ((id >> shift) & 0x1F) as u8) as char)
+        .collect();
+    edid.vendor = PNP_VENDORS
+        .iter()
+        .find(|(pnp, _)| *pnp == edid.vendor_id)
+        .map(|(_, name)| name.to_string());
+    edid.product_code = u16::from_le_bytes([bytes[10], bytes[11]]);
+
+    let serial = u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+    if serial != 0 {
+        edid.serial = Some(serial.to_string());
+    }
+    // Byte 16 is the week, or 0xFF when byte 17 is a model year instead
+    if bytes[17] != 0 {
+        edid.year = Some(1990 + bytes[17] as u16);
+    }
+    if bytes[21] != 0 && bytes[22] != 0 {
+        edid.size_mm = Some((bytes[21] as u32 * 10, bytes[22] as u32 * 10));
+    }
+    let continuous_frequency = bytes[24] & 0x01 != 0;
+
+    let mut range_limits = None;
+    for descriptor in bytes[54..126].chunks_exact(18) {
+        // A non-zero pixel clock makes it a detailed timing; the first one is
+        // the preferred mode
+        if descriptor[0] != 0 || descriptor[1] != 0 {
+            if edid.preferred_mode.is_none()
+                && let Some((mode, size_mm)) = parse_detailed_timing(descriptor)
+            {
+                edid.preferred_mode = Some(mode);
+                // Some panels put the aspect ratio (16x9 mm) here
+                if size_mm.0 >= 100 && size_mm.1 >= 100 {
+                    edid.size_mm = Some(size_mm);
+                }
+            }
+            continue;
+        }
+        match descriptor[3] {
+            0xFC => edid.name = descriptor_text(descriptor),
+            0xFF => edid.serial = descriptor_text(descriptor).or(edid.serial.take()),
+            0xFD => {
+                // Byte 4 bits 1:0 add 255 to the max (10) or both (11) rates
+                let offsets = descriptor[4] & 0x03;
+                let min = descriptor[5] as u32 + if offsets == 0x03 { 255 } else { 0 };
+                let max = descriptor[6] as u32 + if offsets & 0x02 != 0 { 255 } else { 0 };
+                range_limits = Some(VrrRange {
+                    min_hz: min,
+                    max_hz: max,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let extensions = bytes[126] as usize;
+    for block in bytes[128..].chunks_exact(128).take(extensions) {
+        if block[0] == 0x02 {
+            parse_cta_block(block, &mut edid);
+        }
+    }
+
+    // Without a vendor VRR block, a continuous-frequency display's range
+    // limits describe its adaptive sync range
+    if edid.vrr.is_none()
+        && continuous_frequency
+        && let Some(range) = range_limits
+        && range.max_hz > range.min_hz + 10
+    {
+        edid.vrr = Some(range);
+    }
+
+    Some(edid)
+}
+
+/// Look a PNP ID up in the system's hwdata `pnp.ids`.
+pub fn lookup_pnp_id(root: &Sysroot, id: &str) -> Option<String> {
+    ["/usr/share/hwdata/pnp.ids", "/usr/share/misc/pnp.ids"]
+        .iter()
+        .filter_map(|path| root.read_to_string(path).ok())
+        .find_map(|ids| {
+            ids.lines().find_map(|line| {
+                let (pnp, name) = line.split_once('\t')?;
+                (pnp == id).then(|| name.trim().to_string())
+            })
+        })
+}
+
+/// Text of a display descriptor: up to 13 bytes, terminated by a newline.
+fn descriptor_text(descriptor: &[u8]) -> Option<String> {
+    let text: String = descriptor[5..18]
+        .iter()
+        .take_while(|&&b| b != 0x0A && b != 0x00)
+        .filter(|&&b| (0x20..0x7F).contains(&b))
+        .map(|&b| b as char)
+        .collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Mode and image size in mm from an 18-byte detailed timing descriptor.
+fn parse_detailed_timing(d: &[u8]) -> Option<(Mode, (u32, u32))> {
+    let clock_hz = u16::from_le_bytes([d[0], d[1]]) as f64 * 10_000.0;
+    let width = d[2] as u32 | ((d[4] as u32 & 0xF0) << 4);
+    let h_blank = d[3] as u32 | ((d[4] as u32 & 0x0F) << 8);
+    let height = d[5] as u32 | ((d[7] as u32 & 0xF0) << 4);
+    let v_blank = d[6] as u32 | ((d[7] as u32 & 0x0F) << 8);
+    let width_mm = d[12] as u32 | ((d[14] as u32 & 0xF0) << 4);
+    let height_mm = d[13] as u32 | ((d[14] as u32 & 0x0F) << 8);
+
+    let total = (width + h_blank) * (height + v_blank);
+    if width == 0 || height == 0 || total == 0 {
+        return None;
+    }
+    let mode = Mode {
+        width,
+        height,
+        refresh_hz: clock_hz / total as f64,
+    };
+    Some((mode, (width_mm, height_mm)))
+}
+
+/// Walk a CTA-861 extension's data block collection for HDR static
+/// metadata, colorimetry and the vendor blocks that carry a VRR range.
+fn parse_cta_block(block: &[u8], edid: &mut Edid) {
+    // Byte 2 is where the detailed timings start, i.e. where data blocks end
+    let end = (block[2] as usize).clamp(4, 127);
+    let mut i = 4;
+    while i < end {
+        let tag = block[i] >> 5;
+        let len = (block[i] & 0x1F) as usize;
+        // Data block including its header byte; a length running into the
+        // detailed timings means the block is malformed
+        let Some(data) = block[..end].get(i..=i + len) else {
+            break;
+        };
+        i += len + 1;
+
+        match tag {
+            // Vendor-specific data block, keyed by a little-endian IEEE OUI
+            3 if len >= 3 => match u32::from_le_bytes([data[1], data[2], data[3], 0]) {
+                // HDMI Forum: after the version (PB4), rate and feature flags,
+                // VRRmin in PB10 bits 5:0 and VRRmax in PB10 7:6 + PB11
+                0xC45DD8 if len >= 11 => {
+                    let min_hz = (data[10] & 0x3F) as u32;
+                    let max_hz = ((data[10] as u32 & 0xC0) << 2) | data[11] as u32;
+                    if min_hz > 0 && max_hz > min_hz {
+                        edid.vrr = Some(VrrRange { min_hz, max_hz });
+                    }
+                }
+                // AMD FreeSync: minimum and maximum refresh in PB6 and PB7
+                0x00001A if len >= 7 => {
+                    let (min_hz, max_hz) = (data[6] as u32, data[7] as u32);
+                    if min_hz > 0 && max_hz > min_hz && edid.vrr.is_none() {
+                        edid.vrr = Some(VrrRange { min_hz, max_hz });
+                    }
+                }
+                _ => {}
+            },
+            // Extended tag: the tag code is in the first payload byte
+            7 if len >= 2 => match data[1] {
+                0x05 => {
+                    const NAMES: [&str; 8] = [
+                        "xvYCC601",
+                        "xvYCC709",
+                        "sYCC601",
+                        "opYCC601",
+                        "opRGB",
+                        "BT2020cYCC",
+                        "BT2020YCC",
+                        "BT2020RGB",
+                    ];
+                    edid.colorimetry = (0..8)
+                        .filter(|bit| data[2] & (1 << bit) != 0)
+                        .map(|bit| NAMES[bit].to_string())
+                        .collect();
+                    if data.get(3).is_some_and(|b| b & 0x80 != 0) {
+                        edid.colorimetry.push("DCI-P3".to_string());
+                    }
+                }
+                0x06 => {
+                    const EOTFS: [&str; 4] = ["SDR", "HDR", "PQ", "HLG"];
+                    let eotfs = (0..4)
+                        .filter(|bit| data[2] & (1 << bit) != 0)
+                        .map(|bit| EOTFS[bit].to_string())
+                        .collect();
+                    // Coded luminance: max = 50 * 2^(cv/32), min relative to max
+                    let max = |cv: &u8| 50.0 * 2f64.powf(*cv as f64 / 32.0);
+                    let max_luminance = data.get(4).filter(|cv| **cv != 0).map(max);
+                    let min_luminance = match (max_luminance, data.get(6)) {
+                        (Some(max), Some(cv)) => Some(max * (*cv as f64 / 255.0).powi(2) / 100.0),
+                        _ => None,
+                    };
+                    edid.hdr = Some(HdrMetadata {
+                        eotfs,
+                        max_luminance: max_luminance.map(round2),
+                        max_frame_avg_luminance: data
+                            .get(5)
+                            .filter(|cv| **cv != 0)
+                            .map(max)
+                            .map(round2),
+                        min_luminance: min_luminance.map(|l| (l * 10_000.0).round() / 10_000.0),
+                    });
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+    use std::fs;
+
+    fn fixture_edid(machine: &str, connector: &str) -> Edid {
+        let path = fixtures::root(machine).path(format!("/sys/class/drm/{}/edid", connector));
+        parse_edid(&fs::read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_edid_base_block() {
+        let edid = fixture_edid("arch-desktop", "card1-DP-2");
+        assert_eq!(edid.vendor_id, "AUS");
+        assert_eq!(edid.vendor.as_deref(), Some("ASUS"));
+        assert_eq!(edid.name.as_deref(), Some("PG32UCDM"));
+        assert_eq!(edid.serial.as_deref(), Some("S9LMQS012345"));
+        assert_eq!(edid.year, Some(2024));
+        assert_eq!(edid.size_mm, Some((697, 392)));
+        let mode = edid.preferred_mode.unwrap();
+        assert_eq!((mode.width, mode.height), (3840, 2160));
+        assert_eq!(mode.to_string(), "3840x2160 @ 60 Hz");
+    }
+
+    #[test]
+    fn test_parse_edid_cta_extension() {
+        let edid = fixture_edid("arch-desktop", "card1-DP-2");
+        let hdr = edid.hdr.unwrap();
+        assert_eq!(hdr.eotfs, ["SDR", "PQ"]);
+        assert_eq!(hdr.max_luminance, Some(1015.24));
+        assert_eq!(edid.vrr.unwrap().to_string(), "48-240 Hz");
+        assert_eq!(edid.colorimetry, ["BT2020YCC", "BT2020RGB", "DCI-P3"]);
+
+        // HDMI Forum VRR range rather than the 24-144 Hz range limits, no
+        // HDR block
+        let edid = fixture_edid("arch-desktop", "card1-HDMI-A-1");
+        assert_eq!(edid.vrr.unwrap().to_string(), "48-144 Hz");
+        assert!(edid.hdr.is_none());
+    }
+
+    #[test]
+    fn test_hdmi_forum_vrr_max_above_255() {
+        // ALLM and FVA set in PB9; VRRmax 0x12C = 300 Hz spans PB10 and PB11
+        let mut block = [0u8; 128];
+        block[..4].copy_from_slice(&[0x02, 0x03, 16, 0]);
+        block[4..16].copy_from_slice(&[
+            0x6B,
+            0xD8,
+            0x5D,
+            0xC4,
+            0x01,
+            0x78,
+            0x80,
+            0x00,
+            0x00,
+            0x06,
+            0x40 | 48,
+            0x2C,
+        ]);
+        let mut edid = Edid::default();
+        parse_cta_block(&block, &mut edid);
+        assert_eq!(edid.vrr.unwrap().to_string(), "48-300 Hz");
+    }
+
+    #[test]
+    fn test_cta_data_blocks_stop_at_detailed_timings() {
+        // An AMD FreeSync block whose length runs past the DTD offset
+        let mut block = [0u8; 128];
+        block[..4].copy_from_slice(&[0x02, 0x03, 8, 0]);
+        block[4..12].copy_from_slice(&[0x67, 0x1A, 0x00, 0x00, 0x01, 0x01, 48, 144]);
+        let mut edid = Edid::default();
+        parse_cta_block(&block, &mut edid);
+        assert_eq!(edid.vrr, None);
+
+        block[2] = 12;
+        parse_cta_block(&block, &mut edid);
+        assert_eq!(edid.vrr.unwrap().to_string(), "48-144 Hz");
+    }
+
+    #[test]
+    fn test_parse_edid_laptop_panel() {
+        // No name descriptor, numeric serial, range limits without VRR
+        let edid = fixture_edid("ubuntu-thinkpad", "card1-eDP-1");
+        assert_eq!(edid.vendor_id, "SDC");
+        assert_eq!(edid.name, None);
+        assert_eq!(edid.serial.as_deref(), None);
+        assert_eq!(edid.size_mm, Some((302, 189)));
+        assert_eq!(edid.vrr, None);
+        assert_eq!(
+            edid.preferred_mode.unwrap().to_string(),
+            "2880x1800 @ 120 Hz"
+        );
+    }
+
+    #[test]
+    fn test_parse_edid_rejects_garbage() {
+        assert!(parse_edid(&[0u8; 128]).is_none());
+        assert!(parse_edid(&HEADER).is_none());
+    }
+
+    #[test]
+    fn test_lookup_pnp_id() {
+        let root = fixtures::root("arch-desktop");
+        assert_eq!(
+            lookup_pnp_id(&root, "AUS").as_deref(),
+            Some("ASUSTek COMPUTER INC")
+        );
+        assert_eq!(lookup_pnp_id(&root, "ZZZ"), None);
+    }
+}
@@ -1,225 +1,1267 @@
-use super::helpers::run_cmd;
-use std::fs;
+use super::dimms::MemoryLayout;
+use super::helpers::glob_match;
+use super::runner::CommandRunner;
+use super::sysroot::Sysroot;
+use crate::config::{CpuConfig, DiskConfig, GpuConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
 use sysinfo::{Disks, System};
 
-pub fn get_cpu(sys: &System) -> String {
-    sys.cpus()
-        .first()
-        .map(|cpu| {
-            let brand = cpu.brand().to_string();
-            let cores = sys.cpus().len();
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
 
-            // Try to get max boost frequency from cpufreq (more accurate with PBO)
-            let freq = if let Ok(max_freq) =
-                fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/cpuinfo_max_freq")
-            {
-                // cpuinfo_max_freq is in kHz
-                max_freq.trim().parse::<f64>().unwrap_or(0.0) / 1_000_000.0
-            } else {
-                // Fallback to sysinfo
-                cpu.frequency() as f64 / 1000.0
-            };
-
-            // Clean up CPU name
-            let clean_brand = brand
-                .replace("(R)", "")
-                .replace("(TM)", "")
-                .replace("CPU ", "")
-                .replace("  ", " ")
+/// The processor, with its topology and the optional live readings.
+#[derive(Clone, Debug, Serialize)]
+pub struct CpuInfo {
+    pub name: String,
+    /// Logical CPUs, i.e. hardware threads.
+    pub threads: usize,
+    /// Physical cores across all sockets.
+    pub cores: Option<usize>,
+    pub sockets: Option<usize>,
+    /// Performance and efficiency cores, on hybrid Intel parts only.
+    pub performance_cores: Option<usize>,
+    pub efficiency_cores: Option<usize>,
+    /// Highest boost clock in GHz.
+    pub frequency_ghz: f64,
+    /// Package (or Tctl/Tdie) temperature.
+    pub temperature_celsius: Option<f64>,
+    pub governor: Option<String>,
+    pub energy_performance_preference: Option<String>,
+    pub usage_percent: Option<f64>,
+}
+
+impl fmt::Display for CpuInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (", self.name)?;
+        match (self.cores, self.performance_cores, self.efficiency_cores) {
+            (_, Some(p), Some(e)) => write!(f, "{}P+{}E/{}T", p, e, self.threads)?,
+            (Some(cores), _, _) => write!(f, "{}C/{}T", cores, self.threads)?,
+            _ => write!(f, "{}T", self.threads)?,
+        }
+        if let Some(sockets) = self.sockets.filter(|&s| s > 1) {
+            write!(f, ", {} sockets", sockets)?;
+        }
+        write!(f, ") @ {:.2} GHz", self.frequency_ghz)?;
+
+        let mut details = Vec::new();
+        if let Some(temp) = self.temperature_celsius {
+            details.push(format!("{:.0}°C", temp));
+        }
+        if let Some(usage) = self.usage_percent {
+            details.push(format!("{:.0}%", usage));
+        }
+        match (&self.governor, &self.energy_performance_preference) {
+            (Some(governor), Some(epp)) => details.push(format!("{}/{}", governor, epp)),
+            (Some(governor), None) => details.push(governor.clone()),
+            _ => {}
+        }
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a GPU shares system memory or sits on its own card.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuKind {
+    Integrated,
+    Discrete,
+}
+
+/// A detected graphics adapter.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GpuInfo {
+    pub name: String,
+    pub vendor: String,
+    pub kind: GpuKind,
+    /// Bound kernel driver, e.g. `amdgpu`, `nvidia`, `i915`.
+    pub driver: Option<String>,
+    /// PCI address, e.g. `0000:01:00.0`.
+    pub pci_address: Option<String>,
+    /// Dedicated video memory; for an iGPU, the carve-out from system RAM.
+    pub vram_total_bytes: Option<u64>,
+    pub vram_used_bytes: Option<u64>,
+    /// Version of the bound kernel driver, when it reports one.
+    pub driver_version: Option<String>,
+    /// How busy the GPU is right now, 0-100.
+    pub busy_percent: Option<u8>,
+}
+
+impl fmt::Display for GpuInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tag = match self.kind {
+            GpuKind::Integrated => "[iGPU]",
+            GpuKind::Discrete => "[dGPU]",
+        };
+        write!(f, "{} {}", self.name, tag)?;
+
+        let mut details = Vec::new();
+        match (self.vram_used_bytes, self.vram_total_bytes) {
+            (Some(used), Some(total)) => details.push(format!(
+                "{:.2} GiB / {:.2} GiB VRAM",
+                used as f64 / GIB,
+                total as f64 / GIB
+            )),
+            (None, Some(total)) => details.push(format!("{:.2} GiB VRAM", total as f64 / GIB)),
+            _ => {}
+        }
+        if let Some(busy) = self.busy_percent {
+            details.push(format!("{}% busy", busy));
+        }
+        if let (Some(driver), Some(version)) = (&self.driver, &self.driver_version) {
+            details.push(format!("{} {}", driver, version));
+        }
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Used/total byte counts for RAM or swap, plus the RAM speed when known.
+#[derive(Clone, Debug, Serialize)]
+pub struct MemoryInfo {
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+    pub speed_mts: Option<u32>,
+    /// Slots and modules, in memory detail mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<MemoryLayout>,
+}
+
+impl fmt::Display for MemoryInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let used = self.used_bytes as f64 / GIB;
+        let total = self.total_bytes as f64 / GIB;
+        let percent = if total > 0.0 {
+            (used / total * 100.0) as u64
+        } else {
+            0
+        };
+        write!(f, "{:.2} GiB / {:.2} GiB ({}%)", used, total, percent)?;
+        if let Some(speed) = self.speed_mts {
+            write!(f, " @ {} MT/s", speed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Usage of a mounted filesystem.
+#[derive(Clone, Debug, Serialize)]
+pub struct DiskInfo {
+    pub mount_point: String,
+    pub filesystem: String,
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl fmt::Display for DiskInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total_bytes as f64 / GIB;
+        let used = self.used_bytes as f64 / GIB;
+        let percent = if total > 0.0 {
+            (used / total * 100.0) as u64
+        } else {
+            0
+        };
+
+        // Use TiB for large disks
+        if total >= 1024.0 {
+            write!(
+                f,
+                "({}) {:.2} TiB / {:.2} TiB ({}%) - {}",
+                self.mount_point,
+                used / 1024.0,
+                total / 1024.0,
+                percent,
+                self.filesystem
+            )
+        } else {
+            write!(
+                f,
+                "({}) {:.2} GiB / {:.2} GiB ({}%) - {}",
+                self.mount_point, used, total, percent, self.filesystem
+            )
+        }
+    }
+}
+
+pub fn get_cpu(root: &Sysroot, sys: &System) -> Option<CpuInfo> {
+    // Prefer /proc/cpuinfo so a sysroot capture describes its own CPU
+    let cpuinfo = root.read_to_string("/proc/cpuinfo").unwrap_or_default();
+    let (brand, threads, cpuinfo_mhz) = parse_cpuinfo(&cpuinfo);
+
+    let first = sys.cpus().first();
+    let brand = brand.or_else(|| first.map(|cpu| cpu.brand().to_string()))?;
+    let threads = if threads > 0 {
+        threads
+    } else {
+        sys.cpus().len()
+    };
+
+    // Try to get max boost frequency from cpufreq (more accurate with PBO)
+    let freq = if let Ok(max_freq) =
+        root.read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/cpuinfo_max_freq")
+    {
+        // cpuinfo_max_freq is in kHz
+        max_freq.trim().parse::<f64>().unwrap_or(0.0) / 1_000_000.0
+    } else if let Some(mhz) = cpuinfo_mhz {
+        mhz / 1000.0
+    } else {
+        // Fallback to sysinfo
+        first
+            .map(|cpu| cpu.frequency() as f64 / 1000.0)
+            .unwrap_or(0.0)
+    };
+
+    // Clean up CPU name
+    let clean_brand = brand
+        .replace("(R)", "")
+        .replace("(TM)", "")
+        .replace("CPU ", "")
+        .replace("  ", " ")
+        .trim()
+        .to_string();
+
+    let topology = read_cpu_topology(root).or_else(|| cpuinfo_topology(&cpuinfo));
+    let (performance_cores, efficiency_cores) = topology
+        .as_ref()
+        .and_then(|t| hybrid_core_counts(root, t))
+        .unzip();
+
+    Some(CpuInfo {
+        name: clean_brand,
+        threads,
+        cores: topology.as_ref().map(|t| t.cores()),
+        sockets: topology.as_ref().map(|t| t.sockets()),
+        performance_cores,
+        efficiency_cores,
+        // Two decimals is all the display uses, and keeps JSON tidy
+        frequency_ghz: (freq * 100.0).round() / 100.0,
+        temperature_celsius: None,
+        governor: None,
+        energy_performance_preference: None,
+        usage_percent: None,
+    })
+}
+
+/// Fill in the CPU readings enabled in `config`.
+pub fn fill_cpu_details(root: &Sysroot, cpu: &mut CpuInfo, config: &CpuConfig) {
+    if config.temperature {
+        cpu.temperature_celsius = super::sensors::cpu_temperature(root);
+    }
+    if config.governor {
+        let cpufreq = "/sys/devices/system/cpu/cpu0/cpufreq";
+        cpu.governor = root
+            .read_trimmed(format!("{}/scaling_governor", cpufreq))
+            .filter(|g| !g.is_empty());
+        cpu.energy_performance_preference = root
+            .read_trimmed(format!("{}/energy_performance_preference", cpufreq))
+            .filter(|e| !e.is_empty());
+    }
+    if config.usage {
+        cpu.usage_percent = sample_cpu_usage(root, CPU_USAGE_SAMPLE);
+    }
+}
+
+/// How long `usage` watches `/proc/stat`. Long enough for a stable figure,
+/// short enough to fit well inside the default time budget.
+const CPU_USAGE_SAMPLE: Duration = Duration::from_millis(200);
+
+/// Which core and socket each logical CPU belongs to.
+#[derive(Debug, Default)]
+struct CpuTopology {
+    /// Logical CPU number to its `(package, die, core)`.
+    cores_by_cpu: HashMap<usize, (u32, u32, u32)>,
+}
+
+impl CpuTopology {
+    fn cores(&self) -> usize {
+        self.cores_by_cpu.values().collect::<HashSet<_>>().len()
+    }
+
+    fn sockets(&self) -> usize {
+        self.cores_by_cpu
+            .values()
+            .map(|(package, _, _)| package)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Distinct cores among the logical CPUs in `cpus`.
+    fn cores_among(&self, cpus: &[usize]) -> usize {
+        cpus.iter()
+            .filter_map(|cpu| self.cores_by_cpu.get(cpu))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+}
+
+fn read_cpu_topology(root: &Sysroot) -> Option<CpuTopology> {
+    let entries = root.read_dir("/sys/devices/system/cpu").ok()?;
+    let mut topology = CpuTopology::default();
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(cpu) = name.strip_prefix("cpu").and_then(|n| n.parse().ok()) else {
+            continue;
+        };
+        // Offline CPUs have no topology directory
+        let dir = entry.path().join("topology");
+        let id = |file: &str| -> Option<u32> {
+            std::fs::read_to_string(dir.join(file))
+                .ok()?
                 .trim()
-                .to_string();
+                .parse()
+                .ok()
+        };
+        let Some(core) = id("core_id") else {
+            continue;
+        };
+        let package = id("physical_package_id").unwrap_or(0);
+        let die = id("die_id").unwrap_or(0);
+        topology.cores_by_cpu.insert(cpu, (package, die, core));
+    }
 
-            format!("{} ({}) @ {:.2} GHz", clean_brand, cores, freq)
-        })
-        .unwrap_or_else(|| "Unknown".to_string())
+    (!topology.cores_by_cpu.is_empty()).then_some(topology)
 }
 
-pub fn get_gpu() -> Vec<String> {
-    let mut gpus = Vec::new();
+/// The same from `/proc/cpuinfo`'s `physical id` and `core id`, for
+/// containers and chroots that don't mount `/sys`.
+fn cpuinfo_topology(cpuinfo: &str) -> Option<CpuTopology> {
+    let mut topology = CpuTopology::default();
+    let mut cpu = None;
+    let mut package = 0;
+
+    for line in cpuinfo.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().parse::<u32>().ok();
+        match key.trim() {
+            "processor" => cpu = value,
+            "physical id" => package = value.unwrap_or(0),
+            "core id" => {
+                if let (Some(cpu), Some(core)) = (cpu, value) {
+                    topology
+                        .cores_by_cpu
+                        .insert(cpu as usize, (package, 0, core));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (!topology.cores_by_cpu.is_empty()).then_some(topology)
+}
+
+/// Performance and efficiency core counts on Intel hybrid CPUs, which
+/// register a separate perf PMU for each core type.
+fn hybrid_core_counts(root: &Sysroot, topology: &CpuTopology) -> Option<(usize, usize)> {
+    let p_cpus = parse_cpu_list(&root.read_trimmed("/sys/devices/cpu_core/cpus")?);
+    let e_cpus = parse_cpu_list(&root.read_trimmed("/sys/devices/cpu_atom/cpus")?);
+    let counts = (topology.cores_among(&p_cpus), topology.cores_among(&e_cpus));
+    (counts.0 > 0 && counts.1 > 0).then_some(counts)
+}
 
-    if let Some(output) = run_cmd("lspci", &["-mm"]) {
-        let lspci = String::from_utf8_lossy(&output.stdout);
-        for line in lspci.lines() {
-            if line.contains("VGA") || line.contains("3D") || line.contains("Display") {
-                // Parse the quoted fields
-                let fields: Vec<&str> = line.split('"').collect();
-                if fields.len() >= 6 {
-                    let vendor = fields[3];
-                    let device = fields[5];
-
-                    // Determine if discrete or integrated
-                    let is_integrated = device.to_lowercase().contains("radeon graphics")
-                        || device.to_lowercase().contains("intel")
-                            && device.to_lowercase().contains("graphics")
-                        || device.to_lowercase().contains("integrated")
-                        || device.to_lowercase().contains("granite ridge");
-
-                    // Clean up vendor name
-                    let clean_vendor = vendor
-                        .replace("NVIDIA Corporation", "NVIDIA")
-                        .replace("Advanced Micro Devices, Inc. [AMD/ATI]", "AMD")
-                        .replace("Intel Corporation", "Intel");
-
-                    // Parse device: "GB202 [GeForce RTX 5090]" -> "GeForce RTX 5090 [GB202]"
-                    // Fixed: use if-let instead of unwrap() for safety
-                    let gpu_name = if let (Some(bracket_start), Some(bracket_end)) =
-                        (device.find('['), device.find(']'))
-                    {
-                        let chip = device[..bracket_start].trim();
-                        let product = &device[bracket_start + 1..bracket_end];
-
-                        if is_integrated {
-                            format!(
-                                "{} {} [{}]",
-                                clean_vendor,
-                                product.replace(" Graphics", ""),
-                                chip
-                            )
-                        } else {
-                            format!("{} {} [{}]", clean_vendor, product, chip)
-                        }
-                    } else {
-                        format!("{} {}", clean_vendor, device)
-                    };
-
-                    let gpu_type = if is_integrated { "[iGPU]" } else { "[dGPU]" };
-                    gpus.push(format!("{} {}", gpu_name, gpu_type));
+/// `0-11,16,18-19` into the CPU numbers it names.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for range in list.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) {
+                    cpus.extend(start..=end);
                 }
             }
+            None => cpus.extend(range.parse::<usize>().ok()),
         }
     }
+    cpus
+}
 
-    if gpus.is_empty() {
-        gpus.push("Unknown".to_string());
+/// Busy share of all CPUs over `interval`, from two reads of `/proc/stat`.
+fn sample_cpu_usage(root: &Sysroot, interval: Duration) -> Option<f64> {
+    let before = parse_proc_stat(&root.read_to_string("/proc/stat").ok()?)?;
+    std::thread::sleep(interval);
+    let after = parse_proc_stat(&root.read_to_string("/proc/stat").ok()?)?;
+    cpu_usage_between(before, after)
+}
+
+/// `(idle, total)` jiffies from the aggregate `cpu` line of `/proc/stat`.
+/// iowait counts as idle.
+fn parse_proc_stat(content: &str) -> Option<(u64, u64)> {
+    let line = content.lines().find(|line| line.starts_with("cpu "))?;
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    if fields.len() < 4 {
+        return None;
+    }
+    // guest and guest_nice are already included in user and nice
+    let total = fields.iter().take(8).sum();
+    let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+    Some((idle, total))
+}
+
+fn cpu_usage_between(before: (u64, u64), after: (u64, u64)) -> Option<f64> {
+    let total = after.1.checked_sub(before.1)?;
+    let idle = after.0.checked_sub(before.0)?;
+    if total == 0 {
+        return None;
+    }
+    Some(total.saturating_sub(idle) as f64 / total as f64 * 100.0)
+}
+
+/// Pull the model name, logical CPU count and first `cpu MHz` out of
+/// `/proc/cpuinfo`.
+fn parse_cpuinfo(content: &str) -> (Option<String>, usize, Option<f64>) {
+    let mut brand = None;
+    let mut count = 0;
+    let mut mhz = None;
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "processor" => count += 1,
+            "model name" if brand.is_none() => brand = Some(value.trim().to_string()),
+            "cpu MHz" if mhz.is_none() => mhz = value.trim().parse().ok(),
+            _ => {}
+        }
+    }
+
+    (brand, count, mhz)
+}
+
+/// Read `/proc/meminfo` into a map of field name to bytes.
+fn read_meminfo(root: &Sysroot) -> HashMap<String, u64> {
+    let content = root.read_to_string("/proc/meminfo").unwrap_or_default();
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let kib: u64 = value.split_whitespace().next()?.parse().ok()?;
+            Some((key.to_string(), kib * 1024))
+        })
+        .collect()
+}
+
+/// Graphics adapters from `/sys/bus/pci/devices`, in PCI address order.
+///
+/// Names come from the system's `pci.ids`, or from `lspci -mm` where hwdata
+/// isn't installed (NixOS keeps it out of `/usr/share`).
+pub fn get_gpu(root: &Sysroot, cmd: &dyn CommandRunner) -> Vec<GpuInfo> {
+    let devices = read_pci_devices(root);
+    let pci_ids = PCI_IDS_PATHS
+        .iter()
+        .find_map(|path| root.read_to_string(path).ok());
+    let mut lspci = None;
+
+    let mut gpus = Vec::new();
+    for device in devices.iter().filter(|d| d.class >> 16 == 0x03) {
+        let kind = classify_gpu(device, &devices);
+
+        let mut names = pci_ids
+            .as_deref()
+            .and_then(|ids| lookup_pci_ids(ids, device.vendor_id, device.device_id));
+        if names.as_ref().is_none_or(|(_, device)| device.is_none()) {
+            let lspci = lspci.get_or_insert_with(|| cmd.run("lspci", &["-mm"]));
+            names = lspci
+                .as_deref()
+                .and_then(|out| lspci_names(out, &device.address))
+                .or(names);
+        }
+        let (vendor, product) = match names {
+            Some((vendor, Some(product))) => (vendor, product),
+            Some((vendor, None)) => (vendor, format!("Device {:04x}", device.device_id)),
+            None => (
+                format!("{:04x}", device.vendor_id),
+                format!("Device {:04x}", device.device_id),
+            ),
+        };
+
+        let (vendor, name) = format_gpu_name(&vendor, &product, kind);
+        gpus.push(GpuInfo {
+            name,
+            vendor,
+            kind,
+            driver: device.driver.clone(),
+            pci_address: Some(device.address.clone()),
+            vram_total_bytes: None,
+            vram_used_bytes: None,
+            driver_version: None,
+            busy_percent: None,
+        });
     }
 
     gpus
 }
 
-pub fn get_memory(sys: &System) -> String {
-    let used = sys.used_memory() as f64 / 1024.0 / 1024.0 / 1024.0;
-    let total = sys.total_memory() as f64 / 1024.0 / 1024.0 / 1024.0;
-    let percent = (used / total * 100.0) as u64;
+/// Fill in the per-GPU details enabled in `config`.
+///
+/// Kept apart from [`get_gpu`] because VRAM use and load change from one run
+/// to the next, while the adapter list is cached until reboot. amdgpu exposes
+/// both in sysfs; NVIDIA's driver only reports them through `nvidia-smi`.
+pub fn fill_gpu_details(
+    root: &Sysroot,
+    cmd: &dyn CommandRunner,
+    gpus: &mut [GpuInfo],
+    config: &GpuConfig,
+) {
+    let mut nvidia_smi = None;
 
-    // Try to get RAM speed
-    if let Some(speed) = get_ram_speed() {
-        format!(
-            "{:.2} GiB / {:.2} GiB ({}%) @ {} MT/s",
-            used, total, percent, speed
-        )
-    } else {
-        format!("{:.2} GiB / {:.2} GiB ({}%)", used, total, percent)
-    }
-}
-
-fn get_ram_speed() -> Option<u32> {
-    // Try dmidecode (requires root, but might be cached)
-    if let Some(output) = run_cmd("dmidecode", &["-t", "memory"]) {
-        let dmi = String::from_utf8_lossy(&output.stdout);
-        let mut configured_speed: Option<u32> = None;
-        let mut base_speed: Option<u32> = None;
-
-        for line in dmi.lines() {
-            let line = line.trim();
-            // Prefer "Configured Memory Speed:" (actual running speed with XMP/EXPO)
-            if line.starts_with("Configured Memory Speed:") {
-                if let Some(speed_str) = line.split(':').nth(1) {
-                    let speed_str = speed_str.trim().replace(" MT/s", "").replace(" MHz", "");
-                    if let Ok(speed) = speed_str.parse::<u32>()
-                        && speed > 0
-                        && speed < 100000
-                    {
-                        configured_speed = Some(speed);
-                    }
-                }
-            // Fallback to "Speed:" (JEDEC base speed)
-            } else if line.starts_with("Speed:")
-                && line.contains("MT/s")
-                && base_speed.is_none()
-                && let Some(speed_str) = line.split(':').nth(1)
+    for gpu in gpus.iter_mut() {
+        let Some(address) = gpu.pci_address.clone() else {
+            continue;
+        };
+        let device = format!("/sys/bus/pci/devices/{}", address);
+        let read_number = |file: &str| -> Option<u64> {
+            root.read_trimmed(format!("{}/{}", device, file))?
+                .parse()
+                .ok()
+        };
+
+        if config.vram {
+            gpu.vram_total_bytes = read_number("mem_info_vram_total");
+            gpu.vram_used_bytes = read_number("mem_info_vram_used");
+        }
+        if config.load {
+            gpu.busy_percent = read_number("gpu_busy_percent").map(|p| p.min(100) as u8);
+        }
+
+        if gpu.driver.as_deref() == Some("nvidia") && (config.vram || config.load) {
+            let output = nvidia_smi.get_or_insert_with(|| {
+                cmd.run(
+                    "nvidia-smi",
+                    &[
+                        "--query-gpu=pci.bus_id,memory.total,memory.used,utilization.gpu",
+                        "--format=csv,noheader,nounits",
+                    ],
+                )
+            });
+            if let Some(stats) = output
+                .as_deref()
+                .and_then(|out| parse_nvidia_smi(out, &address))
             {
-                let speed_str = speed_str.trim().replace(" MT/s", "").replace(" MHz", "");
-                if let Ok(speed) = speed_str.parse::<u32>()
-                    && speed > 0
-                    && speed < 100000
-                {
-                    base_speed = Some(speed);
+                if config.vram {
+                    gpu.vram_total_bytes = stats.total_bytes;
+                    gpu.vram_used_bytes = stats.used_bytes;
+                }
+                if config.load {
+                    gpu.busy_percent = stats.busy_percent;
                 }
             }
         }
 
-        // Return configured speed if available (XMP/EXPO), otherwise base speed
-        if configured_speed.is_some() {
-            return configured_speed;
+        if config.driver_version
+            && let Some(driver) = gpu.driver.as_deref()
+        {
+            gpu.driver_version = driver_version(root, driver);
+        }
+    }
+}
+
+/// Out-of-tree drivers (nvidia, vboxvideo) publish a module version; in-tree
+/// ones like amdgpu and i915 are versioned with the kernel and don't.
+fn driver_version(root: &Sysroot, driver: &str) -> Option<String> {
+    if let Some(version) = root.read_trimmed(format!("/sys/module/{}/version", driver))
+        && !version.is_empty()
+    {
+        return Some(version);
+    }
+    if driver == "nvidia" {
+        let proc_version = root.read_to_string("/proc/driver/nvidia/version").ok()?;
+        return parse_nvidia_proc_version(&proc_version);
+    }
+    None
+}
+
+/// `NVRM version: NVIDIA UNIX Open Kernel Module for x86_64  580.105.08  Release Build  (...)`
+fn parse_nvidia_proc_version(content: &str) -> Option<String> {
+    let line = content
+        .lines()
+        .find_map(|line| line.strip_prefix("NVRM version:"))?;
+    line.split_whitespace()
+        .find(|word| word.contains('.') && word.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        .map(str::to_string)
+}
+
+#[derive(Debug, PartialEq)]
+struct NvidiaStats {
+    total_bytes: Option<u64>,
+    used_bytes: Option<u64>,
+    busy_percent: Option<u8>,
+}
+
+/// The row for `address` in `nvidia-smi --query-gpu=pci.bus_id,memory.total,
+/// memory.used,utilization.gpu --format=csv,noheader,nounits` output. Memory
+/// is in MiB; fields the card can't report read `[N/A]`.
+fn parse_nvidia_smi(output: &str, address: &str) -> Option<NvidiaStats> {
+    // nvidia-smi pads the PCI domain to eight digits and uses upper case
+    let slot = address.split_once(':').map_or(address, |(_, slot)| slot);
+    output.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [bus_id, total, used, busy] = fields[..] else {
+            return None;
+        };
+        let bus_slot = bus_id.split_once(':').map_or(bus_id, |(_, slot)| slot);
+        if !bus_slot.eq_ignore_ascii_case(slot) {
+            return None;
+        }
+        let mib = |value: &str| value.parse::<u64>().ok().map(|mib| mib * 1024 * 1024);
+        Some(NvidiaStats {
+            total_bytes: mib(total),
+            used_bytes: mib(used),
+            busy_percent: busy.parse::<u8>().ok().map(|p| p.min(100)),
+        })
+    })
+}
+
+/// Where distributions install hwdata's `pci.ids`.
+const PCI_IDS_PATHS: &[&str] = &[
+    "/usr/share/hwdata/pci.ids",
+    "/usr/share/misc/pci.ids",
+    "/usr/share/pci.ids",
+    "/run/current-system/sw/share/hwdata/pci.ids",
+];
+
+const PCI_VENDOR_AMD: u16 = 0x1002;
+const PCI_VENDOR_NVIDIA: u16 = 0x10de;
+const PCI_VENDOR_INTEL: u16 = 0x8086;
+
+/// The parts of a `/sys/bus/pci/devices` entry GPU detection needs.
+#[derive(Debug)]
+struct PciDevice {
+    /// `0000:01:00.0`
+    address: String,
+    class: u32,
+    vendor_id: u16,
+    device_id: u16,
+    driver: Option<String>,
+    /// PCI bridges between the root complex and the device.
+    bridges: usize,
+    boot_vga: bool,
+}
+
+impl PciDevice {
+    /// Domain, bus and device, without the function number.
+    fn slot(&self) -> &str {
+        self.address
+            .rsplit_once('.')
+            .map_or(self.address.as_str(), |(slot, _)| slot)
+    }
+
+    fn bus(&self) -> Option<u8> {
+        let bus = self.address.split(':').nth(1)?;
+        u8::from_str_radix(bus, 16).ok()
+    }
+}
+
+fn read_pci_devices(root: &Sysroot) -> Vec<PciDevice> {
+    let Ok(entries) = root.read_dir("/sys/bus/pci/devices") else {
+        return Vec::new();
+    };
+
+    let hex = |path: &std::path::Path| {
+        let value = std::fs::read_to_string(path).ok()?;
+        u32::from_str_radix(value.trim().trim_start_matches("0x"), 16).ok()
+    };
+    let mut devices: Vec<PciDevice> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let address = entry.file_name().to_string_lossy().to_string();
+            // The entry links to /sys/devices/pci0000:00/<bridge>/.../<address>;
+            // every PCI address on the way down is a bridge
+            let bridges = std::fs::read_link(&path)
+                .map(|target| {
+                    target
+                        .iter()
+                        .filter(|c| is_pci_address(&c.to_string_lossy()))
+                        .count()
+                        .saturating_sub(1)
+                })
+                .unwrap_or(0);
+            Some(PciDevice {
+                class: hex(&path.join("class"))?,
+                vendor_id: hex(&path.join("vendor"))? as u16,
+                device_id: hex(&path.join("device"))? as u16,
+                driver: std::fs::read_link(path.join("driver"))
+                    .ok()
+                    .and_then(|d| d.file_name().map(|n| n.to_string_lossy().to_string())),
+                boot_vga: std::fs::read_to_string(path.join("boot_vga"))
+                    .is_ok_and(|v| v.trim() == "1"),
+                bridges,
+                address,
+            })
+        })
+        .collect();
+    devices.sort_by(|a, b| a.address.cmp(&b.address));
+    devices
+}
+
+/// `0000:01:00.0`
+fn is_pci_address(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 12
+        && bytes[4] == b':'
+        && bytes[7] == b':'
+        && bytes[10] == b'.'
+        && s.chars()
+            .enumerate()
+            .all(|(i, c)| matches!(i, 4 | 7 | 10) || c.is_ascii_hexdigit())
+}
+
+/// Integrated or discrete, from the driver (or vendor) and where the device
+/// sits on the PCI bus.
+fn classify_gpu(gpu: &PciDevice, devices: &[PciDevice]) -> GpuKind {
+    let vendor = match gpu.driver.as_deref() {
+        Some("nvidia" | "nouveau" | "nvidia-drm") => PCI_VENDOR_NVIDIA,
+        Some("i915" | "xe") => PCI_VENDOR_INTEL,
+        Some("amdgpu" | "radeon") => PCI_VENDOR_AMD,
+        _ => gpu.vendor_id,
+    };
+    let on_root_bus = gpu.bus() == Some(0);
+
+    let integrated = match vendor {
+        PCI_VENDOR_NVIDIA => false,
+        // Intel's iGPU is always 00:02.0; Arc cards sit behind a bridge
+        PCI_VENDOR_INTEL => on_root_bus,
+        // A Radeon card sits behind its own PCIe switch, or alone on its bus
+        // with its HDMI audio. An APU's iGPU shares its bus with the chip's
+        // other functions (PSP, USB controllers).
+        PCI_VENDOR_AMD => {
+            gpu.bridges < 2
+                && devices.iter().any(|d| {
+                    d.slot() == gpu.slot() && d.class >> 16 != 0x03 && d.class >> 8 != 0x0403
+                })
+        }
+        // Virtual GPUs and server BMCs: on-board unless plugged in behind a
+        // bridge
+        _ => on_root_bus || (gpu.boot_vga && gpu.bridges == 0),
+    };
+
+    if integrated {
+        GpuKind::Integrated
+    } else {
+        GpuKind::Discrete
+    }
+}
+
+/// Vendor and device names from `pci.ids`. The device is `None` when only
+/// the vendor is listed.
+fn lookup_pci_ids(ids: &str, vendor_id: u16, device_id: u16) -> Option<(String, Option<String>)> {
+    let vendor_hex = format!("{:04x}", vendor_id);
+    let device_hex = format!("{:04x}", device_id);
+    let mut vendor = None;
+
+    for line in ids.lines() {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
         }
-        if base_speed.is_some() {
-            return base_speed;
+        if let Some(device_line) = line.strip_prefix('\t') {
+            // Subsystem lines are indented twice
+            if vendor.is_some()
+                && !device_line.starts_with('\t')
+                && let Some(name) = device_line
+                    .strip_prefix(&device_hex)
+                    .and_then(|rest| rest.strip_prefix("  "))
+            {
+                return Some((vendor?, Some(name.trim().to_string())));
+            }
+        } else if vendor.is_some() {
+            break;
+        } else if let Some(name) = line
+            .strip_prefix(&vendor_hex)
+            .and_then(|rest| rest.strip_prefix("  "))
+        {
+            vendor = Some(name.trim().to_string());
         }
     }
+    vendor.map(|vendor| (vendor, None))
+}
 
-    // Note: Removed dead code branches that never returned useful data:
-    // - /sys/devices/system/edac/mc reads (incomplete implementation)
-    // - lshw fallback (incomplete implementation)
+/// Vendor and device names for `address` from `lspci -mm` output, whose
+/// slots leave out the `0000:` domain.
+fn lspci_names(lspci: &str, address: &str) -> Option<(String, Option<String>)> {
+    let slot = address.strip_prefix("0000:").unwrap_or(address);
+    let line = lspci.lines().find(|line| line.starts_with(slot))?;
+    let fields: Vec<&str> = line.split('"').collect();
+    if fields.len() < 6 {
+        return None;
+    }
+    Some((fields[3].to_string(), Some(fields[5].to_string())))
+}
 
-    None
+/// Short vendor name and display name from pci.ids-style names:
+/// "GB202 [GeForce RTX 5090]" becomes "NVIDIA GeForce RTX 5090 [GB202]".
+fn format_gpu_name(vendor: &str, device: &str, kind: GpuKind) -> (String, String) {
+    let vendor = vendor
+        .replace("NVIDIA Corporation", "NVIDIA")
+        .replace("Advanced Micro Devices, Inc. [AMD/ATI]", "AMD")
+        .replace("Intel Corporation", "Intel");
+
+    let name =
+        if let (Some(bracket_start), Some(bracket_end)) = (device.find('['), device.find(']')) {
+            let chip = device[..bracket_start].trim();
+            let product = &device[bracket_start + 1..bracket_end];
+            // pci.ids sometimes repeats the vendor: "Meteor Lake-P [Intel Arc Graphics]"
+            let product = product
+                .strip_prefix(vendor.as_str())
+                .map_or(product, str::trim_start);
+
+            if kind == GpuKind::Integrated {
+                format!("{} {} [{}]", vendor, product.replace(" Graphics", ""), chip)
+            } else {
+                format!("{} {} [{}]", vendor, product, chip)
+            }
+        } else {
+            format!("{} {}", vendor, device)
+        };
+    (vendor, name)
 }
 
-pub fn get_swap(sys: &System) -> Option<String> {
-    let total = sys.total_swap();
+/// Current RAM usage. The speed and layout are left empty; they come from
+/// the slower [`get_memory_layout`](super::get_memory_layout), which callers
+/// may cache.
+pub fn get_memory(root: &Sysroot, sys: &System) -> MemoryInfo {
+    let meminfo = read_meminfo(root);
+    if let (Some(&total), Some(&available)) = (meminfo.get("MemTotal"), meminfo.get("MemAvailable"))
+    {
+        return MemoryInfo {
+            used_bytes: total.saturating_sub(available),
+            total_bytes: total,
+            speed_mts: None,
+            layout: None,
+        };
+    }
+
+    MemoryInfo {
+        used_bytes: sys.used_memory(),
+        total_bytes: sys.total_memory(),
+        speed_mts: None,
+        layout: None,
+    }
+}
+
+pub fn get_swap(root: &Sysroot, sys: &System) -> Option<MemoryInfo> {
+    let meminfo = read_meminfo(root);
+    let (total, used) = match (meminfo.get("SwapTotal"), meminfo.get("SwapFree")) {
+        (Some(&total), Some(&free)) => (total, total.saturating_sub(free)),
+        _ => (sys.total_swap(), sys.used_swap()),
+    };
     if total == 0 {
         return None;
     }
-    let used = sys.used_swap() as f64 / 1024.0 / 1024.0 / 1024.0;
-    let total = total as f64 / 1024.0 / 1024.0 / 1024.0;
-    let percent = if total > 0.0 {
-        (used / total * 100.0) as u64
-    } else {
-        0
-    };
-    Some(format!("{:.2} GiB / {:.2} GiB ({}%)", used, total, percent))
+    Some(MemoryInfo {
+        used_bytes: used,
+        total_bytes: total,
+        speed_mts: None,
+        layout: None,
+    })
 }
 
-pub fn get_disks() -> Vec<String> {
+/// Mounted filesystems that pass the `[disks]` filters.
+pub fn get_disks(config: &DiskConfig) -> Vec<DiskInfo> {
     let disks = Disks::new_with_refreshed_list();
     let mut disk_info = Vec::new();
 
     for disk in disks.list() {
         let mount = disk.mount_point().to_string_lossy();
-
-        // Only show important mount points
-        if mount == "/"
-            || mount.starts_with("/home")
-            || mount.starts_with("/data")
-            || mount.starts_with("/mnt")
-            || mount.starts_with("/media")
-        {
-            let total = disk.total_space() as f64 / 1024.0 / 1024.0 / 1024.0;
-            let available = disk.available_space() as f64 / 1024.0 / 1024.0 / 1024.0;
-            let used = total - available;
-            let percent = if total > 0.0 {
-                (used / total * 100.0) as u64
-            } else {
-                0
-            };
-
-            let fs = disk.file_system().to_string_lossy();
-
-            // Use TiB for large disks
-            if total >= 1024.0 {
-                let used_tib = used / 1024.0;
-                let total_tib = total / 1024.0;
-                disk_info.push(format!(
-                    "({}) {:.2} TiB / {:.2} TiB ({}%) - {}",
-                    mount, used_tib, total_tib, percent, fs
-                ));
-            } else {
-                disk_info.push(format!(
-                    "({}) {:.2} GiB / {:.2} GiB ({}%) - {}",
-                    mount, used, total, percent, fs
-                ));
-            }
+        let filesystem = disk.file_system().to_string_lossy();
+        if !show_mount(&mount, &filesystem, &disk.name().to_string_lossy(), config) {
+            continue;
         }
+
+        let total = disk.total_space();
+        disk_info.push(DiskInfo {
+            mount_point: mount.to_string(),
+            filesystem: filesystem.to_string(),
+            used_bytes: total.saturating_sub(disk.available_space()),
+            total_bytes: total,
+        });
     }
 
     disk_info
 }
+
+/// Filesystems with no backing storage of their own, or read-only images
+/// (snaps, live media) that are always 100% full.
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "tmpfs",
+    "devtmpfs",
+    "ramfs",
+    "overlay",
+    "squashfs",
+    "erofs",
+    "proc",
+    "sysfs",
+    "efivarfs",
+    "cgroup2",
+    "fuse.portal",
+    "fuse.gvfsd-fuse",
+];
+
+fn show_mount(mount: &str, filesystem: &str, device: &str, config: &DiskConfig) -> bool {
+    if config.hide_pseudo
+        && (PSEUDO_FILESYSTEMS.contains(&filesystem) || device.starts_with("/dev/loop"))
+    {
+        return false;
+    }
+    config.include.iter().any(|glob| glob_match(glob, mount))
+        && !config.exclude.iter().any(|glob| glob_match(glob, mount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+
+    #[test]
+    fn test_parse_cpuinfo() {
+        let cpuinfo = "processor\t: 0\nmodel name\t: AMD Ryzen 9 9950X3D 16-Core Processor\ncpu MHz\t\t: 3012.442\n\nprocessor\t: 1\nmodel name\t: AMD Ryzen 9 9950X3D 16-Core Processor\ncpu MHz\t\t: 5400.000\n";
+        let (brand, count, mhz) = parse_cpuinfo(cpuinfo);
+        assert_eq!(
+            brand.as_deref(),
+            Some("AMD Ryzen 9 9950X3D 16-Core Processor")
+        );
+        assert_eq!(count, 2);
+        assert_eq!(mhz, Some(3012.442));
+    }
+
+    #[test]
+    fn test_cpu_display() {
+        let cpu = CpuInfo {
+            name: "AMD Ryzen 9 9950X3D 16-Core Processor".to_string(),
+            threads: 32,
+            cores: Some(16),
+            sockets: Some(1),
+            performance_cores: None,
+            efficiency_cores: None,
+            frequency_ghz: 5.75,
+            temperature_celsius: None,
+            governor: None,
+            energy_performance_preference: None,
+            usage_percent: None,
+        };
+        assert_eq!(
+            cpu.to_string(),
+            "AMD Ryzen 9 9950X3D 16-Core Processor (16C/32T) @ 5.75 GHz"
+        );
+
+        let detailed = CpuInfo {
+            temperature_celsius: Some(52.375),
+            usage_percent: Some(7.4),
+            governor: Some("powersave".to_string()),
+            energy_performance_preference: Some("balance_performance".to_string()),
+            ..cpu.clone()
+        };
+        assert_eq!(
+            detailed.to_string(),
+            "AMD Ryzen 9 9950X3D 16-Core Processor (16C/32T) @ 5.75 GHz (52°C, 7%, powersave/balance_performance)"
+        );
+
+        let hybrid = CpuInfo {
+            name: "Intel Core Ultra 7 155H".to_string(),
+            threads: 22,
+            performance_cores: Some(6),
+            efficiency_cores: Some(10),
+            frequency_ghz: 4.8,
+            ..cpu.clone()
+        };
+        assert_eq!(
+            hybrid.to_string(),
+            "Intel Core Ultra 7 155H (6P+10E/22T) @ 4.80 GHz"
+        );
+
+        let unknown_cores = CpuInfo {
+            cores: None,
+            sockets: None,
+            ..cpu
+        };
+        assert!(unknown_cores.to_string().contains("(32T)"));
+    }
+
+    #[test]
+    fn test_get_cpu_topology() {
+        let sys = System::new();
+        let cpu = |machine| get_cpu(&fixtures::root(machine), &sys).unwrap();
+
+        let arch = cpu("arch-desktop");
+        assert_eq!((arch.cores, arch.threads), (Some(16), 32));
+        assert_eq!(arch.performance_cores, None);
+
+        let ubuntu = cpu("ubuntu-thinkpad");
+        assert_eq!(ubuntu.cores, Some(16));
+        assert_eq!(ubuntu.performance_cores, Some(6));
+        assert_eq!(ubuntu.efficiency_cores, Some(10));
+
+        // QEMU's default: one core per socket
+        let fedora = cpu("fedora-vm");
+        assert_eq!(fedora.sockets, Some(4));
+        assert_eq!(
+            fedora.to_string(),
+            "AMD EPYC 7763 64-Core Processor (4C/4T, 4 sockets) @ 2.45 GHz"
+        );
+
+        // No /sys in the container: falls back to /proc/cpuinfo
+        assert_eq!(cpu("alpine-docker").cores, Some(11));
+    }
+
+    #[test]
+    fn test_fill_cpu_details() {
+        let sys = System::new();
+        let all = CpuConfig {
+            temperature: true,
+            governor: true,
+            usage: false,
+        };
+        let root = fixtures::root("gentoo-workstation");
+        let mut cpu = get_cpu(&root, &sys).unwrap();
+        fill_cpu_details(&root, &mut cpu, &all);
+        // zenpower's Tdie, not Tctl
+        assert_eq!(cpu.temperature_celsius, Some(45.125));
+        assert_eq!(cpu.governor.as_deref(), Some("performance"));
+
+        let root = fixtures::root("ubuntu-thinkpad");
+        let mut cpu = get_cpu(&root, &sys).unwrap();
+        fill_cpu_details(&root, &mut cpu, &all);
+        // coretemp's package sensor, past the ACPI thermal zone
+        assert_eq!(cpu.temperature_celsius, Some(47.0));
+        assert_eq!(
+            cpu.energy_performance_preference.as_deref(),
+            Some("balance_power")
+        );
+
+        let mut cpu = get_cpu(&root, &sys).unwrap();
+        fill_cpu_details(&root, &mut cpu, &CpuConfig::default());
+        assert_eq!(cpu.temperature_celsius, None);
+        assert_eq!(cpu.governor, None);
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert!(parse_cpu_list("").is_empty());
+    }
+
+    #[test]
+    fn test_cpu_usage_from_proc_stat() {
+        let before = parse_proc_stat("cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 1 2 3 4\n").unwrap();
+        assert_eq!(before, (850, 1000));
+        let after = parse_proc_stat("cpu  250 0 100 1000 50 0 0 0 0 0\n").unwrap();
+        assert_eq!(cpu_usage_between(before, after), Some(50.0));
+        assert_eq!(cpu_usage_between(after, after), None);
+    }
+
+    #[test]
+    fn test_memory_display() {
+        let mem = MemoryInfo {
+            used_bytes: 2 * 1024 * 1024 * 1024,
+            total_bytes: 8 * 1024 * 1024 * 1024,
+            speed_mts: Some(6000),
+            layout: None,
+        };
+        assert_eq!(mem.to_string(), "2.00 GiB / 8.00 GiB (25%) @ 6000 MT/s");
+
+        let swap = MemoryInfo {
+            speed_mts: None,
+            layout: None,
+            ..mem
+        };
+        assert_eq!(swap.to_string(), "2.00 GiB / 8.00 GiB (25%)");
+    }
+
+    #[test]
+    fn test_disk_display_switches_to_tib() {
+        let disk = DiskInfo {
+            mount_point: "/".to_string(),
+            filesystem: "btrfs".to_string(),
+            used_bytes: 1024 * 1024 * 1024 * 1024,
+            total_bytes: 2 * 1024 * 1024 * 1024 * 1024,
+        };
+        assert_eq!(disk.to_string(), "(/) 1.00 TiB / 2.00 TiB (50%) - btrfs");
+    }
+
+    #[test]
+    fn test_show_mount_filters() {
+        let default = DiskConfig::default();
+        assert!(show_mount("/", "btrfs", "/dev/nvme0n1p2", &default));
+        assert!(show_mount("/home", "btrfs", "/dev/nvme0n1p2", &default));
+        assert!(!show_mount("/srv", "xfs", "/dev/sda1", &default));
+        assert!(!show_mount("/mnt/iso", "iso9660", "/dev/loop0", &default));
+
+        let server = DiskConfig {
+            include: vec![
+                "/".to_string(),
+                "/srv*".to_string(),
+                "/var/lib/*".to_string(),
+            ],
+            exclude: vec!["/var/lib/docker*".to_string()],
+            hide_pseudo: true,
+        };
+        assert!(show_mount("/srv", "xfs", "/dev/sda1", &server));
+        assert!(show_mount("/var/lib/postgres", "xfs", "/dev/sdb1", &server));
+        assert!(!show_mount("/var/lib/docker", "ext4", "/dev/sdc1", &server));
+        assert!(!show_mount("/srv/cache", "tmpfs", "tmpfs", &server));
+
+        let everything = DiskConfig {
+            include: vec!["*".to_string()],
+            hide_pseudo: false,
+            ..server
+        };
+        assert!(show_mount("/srv/cache", "tmpfs", "tmpfs", &everything));
+    }
+
+    #[test]
+    fn test_gpu_display_and_json() {
+        let gpu = GpuInfo {
+            name: "NVIDIA GeForce RTX 5090 [GB202]".to_string(),
+            vendor: "NVIDIA".to_string(),
+            kind: GpuKind::Discrete,
+            driver: Some("nvidia".to_string()),
+            pci_address: Some("0000:01:00.0".to_string()),
+            vram_total_bytes: None,
+            vram_used_bytes: None,
+            driver_version: None,
+            busy_percent: None,
+        };
+        assert_eq!(gpu.to_string(), "NVIDIA GeForce RTX 5090 [GB202] [dGPU]");
+        let json = serde_json::to_string(&gpu).unwrap();
+        assert!(json.contains(r#""kind":"discrete""#));
+
+        let gpu = GpuInfo {
+            vram_total_bytes: Some(32 * 1024 * 1024 * 1024),
+            vram_used_bytes: Some(2 * 1024 * 1024 * 1024),
+            driver_version: Some("580.105.08".to_string()),
+            busy_percent: Some(12),
+            ..gpu
+        };
+        assert_eq!(
+            gpu.to_string(),
+            "NVIDIA GeForce RTX 5090 [GB202] [dGPU] (2.00 GiB / 32.00 GiB VRAM, 12% busy, nvidia 580.105.08)"
+        );
+    }
+
+    #[test]
+    fn test_fill_gpu_details() {
+        let machine = "arch-desktop";
+        let root = fixtures::root(machine);
+        let cmd = fixtures::commands(machine);
+        let mut gpus = get_gpu(&root, &cmd);
+        let all = GpuConfig {
+            vram: true,
+            driver_version: true,
+            load: true,
+        };
+        fill_gpu_details(&root, &cmd, &mut gpus, &all);
+
+        // NVIDIA through nvidia-smi and /proc/driver/nvidia/version
+        assert_eq!(gpus[0].vram_total_bytes, Some(32607 * 1024 * 1024));
+        assert_eq!(gpus[0].vram_used_bytes, Some(1843 * 1024 * 1024));
+        assert_eq!(gpus[0].busy_percent, Some(7));
+        assert_eq!(gpus[0].driver_version.as_deref(), Some("580.105.08"));
+
+        // amdgpu through sysfs; in-tree, so no driver version
+        assert_eq!(gpus[1].vram_total_bytes, Some(536870912));
+        assert_eq!(gpus[1].vram_used_bytes, Some(91226112));
+        assert_eq!(gpus[1].busy_percent, Some(2));
+        assert_eq!(gpus[1].driver_version, None);
+
+        // Only what's enabled is read, and nothing is by default
+        let mut gpus = get_gpu(&root, &cmd);
+        let vram = GpuConfig {
+            vram: true,
+            ..GpuConfig::default()
+        };
+        fill_gpu_details(&root, &cmd, &mut gpus, &vram);
+        assert!(gpus[1].vram_total_bytes.is_some());
+        assert_eq!(gpus[1].busy_percent, None);
+        assert_eq!(gpus[0].driver_version, None);
+
+        let mut gpus = get_gpu(&root, &cmd);
+        fill_gpu_details(&root, &cmd, &mut gpus, &GpuConfig::default());
+        assert!(gpus.iter().all(|gpu| gpu.vram_total_bytes.is_none()));
+    }
+
+    #[test]
+    fn test_parse_nvidia_smi() {
+        let output = "00000000:01:00.0, 32607, 1843, 7\n00000000:41:00.0, 24564, [N/A], [N/A]\n";
+        assert_eq!(
+            parse_nvidia_smi(output, "0000:41:00.0"),
+            Some(NvidiaStats {
+                total_bytes: Some(24564 * 1024 * 1024),
+                used_bytes: None,
+                busy_percent: None,
+            })
+        );
+        assert_eq!(parse_nvidia_smi(output, "0000:02:00.0"), None);
+    }
+
+    #[test]
+    fn test_parse_nvidia_proc_version() {
+        let content = "NVRM version: NVIDIA UNIX Open Kernel Module for x86_64  580.105.08  Release Build  (dvs-builder@U22-I3-AF03-09-1)  Thu Oct  9 20:02:31 UTC 2025\nGCC version:  gcc version 15.2.1 20250813 (GCC)\n";
+        assert_eq!(
+            parse_nvidia_proc_version(content),
+            Some("580.105.08".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_gpu_from_sysfs() {
+        let machine = "arch-desktop";
+        let gpus = get_gpu(&fixtures::root(machine), &fixtures::commands(machine));
+        let names: Vec<String> = gpus.iter().map(|gpu| gpu.to_string()).collect();
+        assert_eq!(
+            names,
+            [
+                "NVIDIA GeForce RTX 5090 [GB202] [dGPU]",
+                "AMD Radeon [Granite Ridge] [iGPU]",
+            ]
+        );
+        assert_eq!(gpus[0].driver.as_deref(), Some("nvidia"));
+        assert_eq!(gpus[1].driver.as_deref(), Some("amdgpu"));
+        assert_eq!(gpus[1].pci_address.as_deref(), Some("0000:7b:00.0"));
+
+        let machine = "ubuntu-thinkpad";
+        let gpus = get_gpu(&fixtures::root(machine), &fixtures::commands(machine));
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].kind, GpuKind::Integrated);
+        assert_eq!(gpus[0].vendor, "Intel");
+        assert_eq!(gpus[0].name, "Intel Arc [Meteor Lake-P]");
+        assert_eq!(gpus[0].driver.as_deref(), Some("i915"));
+    }
+
+    #[test]
+    fn test_get_gpu_names_from_lspci_without_pci_ids() {
+        let machine = "nixos-framework";
+        let gpus = get_gpu(&fixtures::root(machine), &fixtures::commands(machine));
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].to_string(), "AMD Radeon 780M [Phoenix1] [iGPU]");
+    }
+
+    #[test]
+    fn test_lookup_pci_ids() {
+        let ids = "# comment\n10de  NVIDIA Corporation\n\t2b85  GB202 [GeForce RTX 5090]\n\t\t10de 2056  subsystem\n10df  Emulex Corporation\n\t2b86  Not NVIDIA\n";
+        assert_eq!(
+            lookup_pci_ids(ids, 0x10de, 0x2b85),
+            Some((
+                "NVIDIA Corporation".to_string(),
+                Some("GB202 [GeForce RTX 5090]".to_string())
+            ))
+        );
+        assert_eq!(
+            lookup_pci_ids(ids, 0x10de, 0x2b86),
+            Some(("NVIDIA Corporation".to_string(), None))
+        );
+        assert_eq!(lookup_pci_ids(ids, 0x1234, 0x0001), None);
+    }
+}
@@ -1,5 +1,6 @@
+use std::collections::HashSet;
 use std::process::{Command, Output, Stdio};
-use std::sync::mpsc;
+use std::sync::{Mutex, mpsc};
 use std::thread;
 use std::time::Duration;
 
@@ -10,6 +11,37 @@ use std::time::Duration;
 /// can never freeze the whole program.
 const CMD_TIMEOUT: Duration = Duration::from_secs(2);
 
+/// PIDs of helper processes that have been spawned and not yet reaped.
+///
+/// Modules run on detached threads, so when the global collection deadline
+/// passes their helpers may still be running; [`kill_running_cmds`] uses this
+/// set to clean them up instead of leaving orphans behind.
+static RUNNING: Mutex<Option<HashSet<u32>>> = Mutex::new(None);
+
+fn track(pid: u32, running: bool) {
+    if let Ok(mut set) = RUNNING.lock() {
+        let set = set.get_or_insert_with(HashSet::new);
+        if running {
+            set.insert(pid);
+        } else {
+            set.remove(&pid);
+        }
+    }
+}
+
+/// Kill every helper process still running.
+pub fn kill_running_cmds() {
+    if let Ok(mut set) = RUNNING.lock()
+        && let Some(set) = set.as_mut()
+    {
+        for pid in set.drain() {
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+    }
+}
+
 /// Run an external command, returning its output, or `None` if it cannot be
 /// spawned or does not finish within [`CMD_TIMEOUT`].
 pub fn run_cmd(program: &str, args: &[&str]) -> Option<Output> {
@@ -31,15 +63,19 @@ pub fn run_cmd_timeout(program: &str, args: &[&str], timeout: Duration) -> Optio
         .ok()?;
 
     let pid = child.id();
+    track(pid, true);
     let (tx, rx) = mpsc::channel();
     thread::spawn(move || {
-        let _ = tx.send(child.wait_with_output());
+        let result = child.wait_with_output();
+        track(pid, false);
+        let _ = tx.send(result);
     });
 
     match rx.recv_timeout(timeout) {
         Ok(result) => result.ok(),
         Err(_) => {
             // Timed out: kill the child so the worker thread can reap it.
+            track(pid, false);
             unsafe {
                 libc::kill(pid as libc::pid_t, libc::SIGKILL);
             }
@@ -73,6 +109,38 @@ pub fn strip_ansi_codes(s: &str) -> String {
     result
 }
 
+/// Match `text` against a shell-style glob. `*` matches any run of
+/// characters, `/` included, and `?` matches exactly one.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*`: pattern index past it, text index
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
 /// Extract version number from a version string line.
 /// Looks for parts starting with a digit or 'v'.
 #[allow(dead_code)]
@@ -99,6 +167,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("/", "/"));
+        assert!(!glob_match("/", "/boot"));
+        assert!(glob_match("/home*", "/home"));
+        assert!(glob_match("/home*", "/home/alice/media"));
+        assert!(glob_match("/var/lib/*", "/var/lib/docker"));
+        assert!(!glob_match("/var/lib/*", "/var/lib"));
+        assert!(glob_match("/srv/?", "/srv/a"));
+        assert!(glob_match("*snap*", "/var/snap/firefox"));
+        assert!(!glob_match("/mnt", "/mnt2"));
+    }
+
     #[test]
     fn test_parse_version_from_line() {
         assert_eq!(parse_version_from_line("zsh 5.9"), Some("5.9"));
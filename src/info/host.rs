@@ -1,15 +1,18 @@
-use std::fs;
+use super::sysroot::Sysroot;
 
-pub fn get_hostname() -> String {
-    whoami::hostname().unwrap_or_else(|_| "Unknown".to_string())
+pub fn get_hostname(root: &Sysroot) -> String {
+    root.read_trimmed("/proc/sys/kernel/hostname")
+        .or_else(|| root.read_trimmed("/etc/hostname"))
+        .or_else(|| whoami::hostname().ok())
+        .unwrap_or_else(|| "Unknown".to_string())
 }
 
 pub fn get_username() -> String {
     whoami::username().unwrap_or_else(|_| "Unknown".to_string())
 }
 
-pub fn get_uptime() -> String {
-    fs::read_to_string("/proc/uptime")
+pub fn get_uptime(root: &Sysroot) -> String {
+    root.read_to_string("/proc/uptime")
         .ok()
         .and_then(|content| content.split_whitespace().next().map(String::from))
         .and_then(|secs_str| secs_str.parse::<f64>().ok())
@@ -34,16 +37,17 @@ pub fn get_locale() -> String {
     std::env::var("LANG").unwrap_or_else(|_| "Unknown".to_string())
 }
 
-pub fn get_host() -> Option<String> {
+pub fn get_host(root: &Sysroot) -> Option<String> {
     // Try to get product name (laptop/desktop model)
-    if let Ok(product) = fs::read_to_string("/sys/devices/virtual/dmi/id/product_name") {
+    if let Ok(product) = root.read_to_string("/sys/devices/virtual/dmi/id/product_name") {
         let product = product.trim();
         if !product.is_empty()
             && product != "System Product Name"
             && product != "To Be Filled By O.E.M."
         {
             // Also try to get version for some systems
-            if let Ok(version) = fs::read_to_string("/sys/devices/virtual/dmi/id/product_version") {
+            if let Ok(version) = root.read_to_string("/sys/devices/virtual/dmi/id/product_version")
+            {
                 let version = version.trim();
                 if !version.is_empty()
                     && version != "System Version"
@@ -57,10 +61,10 @@ pub fn get_host() -> Option<String> {
     }
 
     // Try board name as fallback (for desktops)
-    if let Ok(board) = fs::read_to_string("/sys/devices/virtual/dmi/id/board_name") {
+    if let Ok(board) = root.read_to_string("/sys/devices/virtual/dmi/id/board_name") {
         let board = board.trim();
         if !board.is_empty() && board != "To Be Filled By O.E.M." {
-            if let Ok(vendor) = fs::read_to_string("/sys/devices/virtual/dmi/id/board_vendor") {
+            if let Ok(vendor) = root.read_to_string("/sys/devices/virtual/dmi/id/board_vendor") {
                 let vendor = vendor.trim();
                 return Some(format!("{} {}", vendor, board));
             }
@@ -1,15 +1,45 @@
+mod dimms;
 mod display;
+mod drives;
+mod edid;
 mod hardware;
 pub mod helpers;
 mod host;
+mod netlink;
 mod network;
+mod packages;
+mod power;
+mod public_ip;
+mod runner;
+mod sensors;
 mod software;
+mod sysroot;
+mod updates;
+mod virt;
+mod vpn;
 
 // Re-export public functions used by main.rs
+pub use dimms::get_memory_layout;
 pub use display::{
-    MonitorInfo, get_de, get_monitors, get_multiplexer, get_terminal, get_terminal_font, get_wm,
+    MonitorInfo, find_terminal, get_de, get_monitors, get_multiplexer, get_terminal,
+    get_terminal_font, get_wm,
+};
+pub use drives::{DriveInfo, get_drives};
+pub use hardware::{
+    CpuInfo, DiskInfo, GpuInfo, MemoryInfo, fill_cpu_details, fill_gpu_details, get_cpu, get_disks,
+    get_gpu, get_memory, get_swap,
 };
-pub use hardware::{get_cpu, get_disks, get_gpu, get_memory, get_swap};
 pub use host::{get_host, get_hostname, get_locale, get_uptime, get_username};
-pub use network::get_local_ip;
-pub use software::{get_editor, get_kernel, get_os_info, get_packages, get_shell, get_shell_theme};
+pub use network::{NetworkInfo, fill_wifi_details, get_local_ip, get_network};
+pub use packages::get_packages;
+pub use power::{PowerInfo, get_power};
+pub use public_ip::{PublicIpInfo, get_public_ip};
+#[cfg(test)]
+pub use runner::fixtures;
+pub use runner::{CommandRunner, RecordingRunner, ReplayRunner, SystemRunner};
+pub use sensors::{SensorsInfo, get_sensors};
+pub use software::{get_editor, get_kernel, get_os_info, get_shell, get_shell_theme};
+pub use sysroot::Sysroot;
+pub use updates::{UpdatesInfo, get_update_candidates, get_updates};
+pub use virt::{VirtInfo, get_virtualization};
+pub use vpn::{VpnInfo, get_vpns};
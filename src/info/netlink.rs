@@ -0,0 +1,359 @@
+//! Minimal rtnetlink client: dumps interface addresses and default routes
+//! from the running kernel without spawning `ip`.
+
+use std::io;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::Duration;
+
+const NLMSG_HEADER_LEN: usize = 16;
+const IFADDRMSG_LEN: usize = 8;
+const RTMSG_LEN: usize = 12;
+const RECV_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// One address from an `RTM_NEWADDR` message.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct NlAddress {
+    pub index: u32,
+    pub addr: IpAddr,
+    pub prefix_len: u8,
+    /// `RT_SCOPE_UNIVERSE` (0) for global addresses.
+    pub scope: u8,
+}
+
+/// A default route in the main table from an `RTM_NEWROUTE` message.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct NlRoute {
+    pub index: u32,
+    pub gateway: IpAddr,
+}
+
+/// Every IPv4 and IPv6 address on the system.
+pub(super) fn dump_addresses() -> io::Result<Vec<NlAddress>> {
+    let mut header = [0u8; IFADDRMSG_LEN];
+    header[0] = libc::AF_UNSPEC as u8;
+    let messages = dump(libc::RTM_GETADDR, &header)?;
+    Ok(messages
+        .iter()
+        .filter(|(kind, _)| *kind == libc::RTM_NEWADDR)
+        .filter_map(|(_, payload)| parse_address(payload))
+        .collect())
+}
+
+/// Default routes with a gateway, IPv4 and IPv6.
+pub(super) fn dump_default_routes() -> io::Result<Vec<NlRoute>> {
+    let mut header = [0u8; RTMSG_LEN];
+    header[0] = libc::AF_UNSPEC as u8;
+    let messages = dump(libc::RTM_GETROUTE, &header)?;
+    Ok(messages
+        .iter()
+        .filter(|(kind, _)| *kind == libc::RTM_NEWROUTE)
+        .filter_map(|(_, payload)| parse_default_route(payload))
+        .collect())
+}
+
+/// Send one dump request and collect `(message type, payload)` for every
+/// reply up to `NLMSG_DONE`.
+fn dump(request_type: u16, header: &[u8]) -> io::Result<Vec<(u16, Vec<u8>)>> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            libc::NETLINK_ROUTE,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let timeout = libc::timeval {
+        tv_sec: 0,
+        tv_usec: RECV_TIMEOUT.as_micros() as libc::suseconds_t,
+    };
+    unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            &timeout as *const libc::timeval as *const libc::c_void,
+            mem::size_of::<libc::timeval>() as libc::socklen_t,
+        );
+    }
+
+    let len = NLMSG_HEADER_LEN + header.len();
+    let mut request = Vec::with_capacity(len);
+    request.extend_from_slice(&(len as u32).to_ne_bytes());
+    request.extend_from_slice(&request_type.to_ne_bytes());
+    request.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    request.extend_from_slice(&1u32.to_ne_bytes()); // sequence
+    request.extend_from_slice(&0u32.to_ne_bytes()); // port id, filled in by the kernel
+    request.extend_from_slice(header);
+
+    let mut kernel: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    kernel.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    let sent = unsafe {
+        libc::sendto(
+            socket.as_raw_fd(),
+            request.as_ptr() as *const libc::c_void,
+            request.len(),
+            0,
+            &kernel as *const libc::sockaddr_nl as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut messages = Vec::new();
+    let mut buf = vec![0u8; 32 * 1024];
+    loop {
+        let received = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                0,
+            )
+        };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if received == 0 {
+            return Ok(messages);
+        }
+        if split_messages(&buf[..received as usize], &mut messages)? {
+            return Ok(messages);
+        }
+    }
+}
+
+/// Append the messages in one datagram. Returns `true` once `NLMSG_DONE`
+/// is seen.
+fn split_messages(mut buf: &[u8], out: &mut Vec<(u16, Vec<u8>)>) -> io::Result<bool> {
+    while buf.len() >= NLMSG_HEADER_LEN {
+        let len = u32::from_ne_bytes(buf[0..4].try_into().unwrap()) as usize;
+        let kind = u16::from_ne_bytes(buf[4..6].try_into().unwrap());
+        if len < NLMSG_HEADER_LEN || len > buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated netlink message",
+            ));
+        }
+        let payload = &buf[NLMSG_HEADER_LEN..len];
+        match kind as libc::c_int {
+            libc::NLMSG_DONE => return Ok(true),
+            libc::NLMSG_ERROR => {
+                let errno = payload
+                    .get(0..4)
+                    .map(|b| i32::from_ne_bytes(b.try_into().unwrap()))
+                    .unwrap_or(0);
+                if errno != 0 {
+                    return Err(io::Error::from_raw_os_error(-errno));
+                }
+            }
+            _ => out.push((kind, payload.to_vec())),
+        }
+        buf = &buf[align(len).min(buf.len())..];
+    }
+    Ok(false)
+}
+
+fn parse_address(payload: &[u8]) -> Option<NlAddress> {
+    let header = payload.get(..IFADDRMSG_LEN)?;
+    let family = header[0] as libc::c_int;
+    let mut local = None;
+    let mut address = None;
+    for (kind, data) in attributes(&payload[IFADDRMSG_LEN..]) {
+        match kind {
+            libc::IFA_LOCAL => local = ip_from(family, data),
+            libc::IFA_ADDRESS => address = ip_from(family, data),
+            _ => {}
+        }
+    }
+    // On point-to-point links IFA_ADDRESS is the peer; IFA_LOCAL is ours
+    Some(NlAddress {
+        index: u32::from_ne_bytes(header[4..8].try_into().unwrap()),
+        addr: local.or(address)?,
+        prefix_len: header[1],
+        scope: header[3],
+    })
+}
+
+fn parse_default_route(payload: &[u8]) -> Option<NlRoute> {
+    let header = payload.get(..RTMSG_LEN)?;
+    let family = header[0] as libc::c_int;
+    let dst_len = header[1];
+    let mut table = header[4] as u32;
+    let route_type = header[7];
+    if dst_len != 0 || route_type != libc::RTN_UNICAST {
+        return None;
+    }
+
+    let mut gateway = None;
+    let mut index = None;
+    for (kind, data) in attributes(&payload[RTMSG_LEN..]) {
+        match kind {
+            libc::RTA_GATEWAY => gateway = ip_from(family, data),
+            libc::RTA_OIF => {
+                index = data
+                    .get(..4)
+                    .map(|b| u32::from_ne_bytes(b.try_into().unwrap()))
+            }
+            libc::RTA_TABLE => {
+                if let Some(b) = data.get(..4) {
+                    table = u32::from_ne_bytes(b.try_into().unwrap());
+                }
+            }
+            _ => {}
+        }
+    }
+    if table != libc::RT_TABLE_MAIN as u32 {
+        return None;
+    }
+    Some(NlRoute {
+        index: index?,
+        gateway: gateway?,
+    })
+}
+
+/// `(type, data)` for each `rtattr` in `buf`.
+fn attributes(mut buf: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attrs = Vec::new();
+    while buf.len() >= 4 {
+        let len = u16::from_ne_bytes([buf[0], buf[1]]) as usize;
+        let kind = u16::from_ne_bytes([buf[2], buf[3]]);
+        if len < 4 || len > buf.len() {
+            break;
+        }
+        attrs.push((kind, &buf[4..len]));
+        buf = &buf[align(len).min(buf.len())..];
+    }
+    attrs
+}
+
+fn ip_from(family: libc::c_int, data: &[u8]) -> Option<IpAddr> {
+    match family {
+        libc::AF_INET => {
+            let octets: [u8; 4] = data.get(..4)?.try_into().ok()?;
+            Some(IpAddr::V4(Ipv4Addr::from(octets)))
+        }
+        libc::AF_INET6 => {
+            let octets: [u8; 16] = data.get(..16)?.try_into().ok()?;
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attr(kind: u16, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&((4 + data.len()) as u16).to_ne_bytes());
+        out.extend_from_slice(&kind.to_ne_bytes());
+        out.extend_from_slice(data);
+        out.resize(align(out.len()), 0);
+        out
+    }
+
+    fn message(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&((NLMSG_HEADER_LEN + payload.len()) as u32).to_ne_bytes());
+        out.extend_from_slice(&kind.to_ne_bytes());
+        out.extend_from_slice(&[0; 10]);
+        out.extend_from_slice(payload);
+        out.resize(align(out.len()), 0);
+        out
+    }
+
+    #[test]
+    fn test_parse_addresses() {
+        // 10.0.0.21/24 on ifindex 4, then a point-to-point wg address
+        let mut lan = vec![libc::AF_INET as u8, 24, 0, 0];
+        lan.extend_from_slice(&4u32.to_ne_bytes());
+        lan.extend(attr(libc::IFA_ADDRESS, &[10, 0, 0, 21]));
+        lan.extend(attr(libc::IFA_LOCAL, &[10, 0, 0, 21]));
+
+        let mut ptp = vec![libc::AF_INET as u8, 32, 0, 0];
+        ptp.extend_from_slice(&9u32.to_ne_bytes());
+        ptp.extend(attr(libc::IFA_ADDRESS, &[10, 8, 0, 1]));
+        ptp.extend(attr(libc::IFA_LOCAL, &[10, 8, 0, 2]));
+
+        let mut v6 = vec![libc::AF_INET6 as u8, 64, 0, 0];
+        v6.extend_from_slice(&4u32.to_ne_bytes());
+        let global: Ipv6Addr = "2001:db8::21".parse().unwrap();
+        v6.extend(attr(libc::IFA_ADDRESS, &global.octets()));
+
+        let mut datagram = message(libc::RTM_NEWADDR, &lan);
+        datagram.extend(message(libc::RTM_NEWADDR, &ptp));
+        datagram.extend(message(libc::RTM_NEWADDR, &v6));
+        datagram.extend(message(libc::NLMSG_DONE as u16, &[0; 4]));
+
+        let mut messages = Vec::new();
+        assert!(split_messages(&datagram, &mut messages).unwrap());
+        let addresses: Vec<NlAddress> = messages
+            .iter()
+            .filter_map(|(_, payload)| parse_address(payload))
+            .collect();
+        assert_eq!(
+            addresses,
+            [
+                NlAddress {
+                    index: 4,
+                    addr: "10.0.0.21".parse().unwrap(),
+                    prefix_len: 24,
+                    scope: 0,
+                },
+                NlAddress {
+                    index: 9,
+                    addr: "10.8.0.2".parse().unwrap(),
+                    prefix_len: 32,
+                    scope: 0,
+                },
+                NlAddress {
+                    index: 4,
+                    addr: IpAddr::V6(global),
+                    prefix_len: 64,
+                    scope: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_default_route() {
+        let route = |dst_len: u8, table: u8| {
+            let mut payload = vec![libc::AF_INET as u8, dst_len, 0, 0, table, 3, 0, 1];
+            payload.extend_from_slice(&0u32.to_ne_bytes());
+            payload.extend(attr(libc::RTA_GATEWAY, &[10, 0, 0, 1]));
+            payload.extend(attr(libc::RTA_OIF, &4u32.to_ne_bytes()));
+            payload
+        };
+        assert_eq!(
+            parse_default_route(&route(0, libc::RT_TABLE_MAIN)),
+            Some(NlRoute {
+                index: 4,
+                gateway: "10.0.0.1".parse().unwrap(),
+            })
+        );
+        // Subnet routes and other tables are not the default route
+        assert_eq!(parse_default_route(&route(24, libc::RT_TABLE_MAIN)), None);
+        assert_eq!(parse_default_route(&route(0, 100)), None);
+    }
+
+    #[test]
+    fn test_netlink_error_is_reported() {
+        let datagram = message(libc::NLMSG_ERROR as u16, &(-libc::EPERM).to_ne_bytes());
+        let err = split_messages(&datagram, &mut Vec::new()).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EPERM));
+    }
+}
@@ -1,71 +1,694 @@
-use super::helpers::run_cmd;
+//! Interfaces, addresses, default routes and DNS servers.
+//!
+//! On the running system addresses and routes come from rtnetlink. When
+//! netlink is unavailable (seccomp sandboxes) or a sysroot points elsewhere,
+//! they are read from `/proc/net` instead. Interface details always come from
+//! `/sys/class/net`; Wi-Fi details come from `iw` and `/proc/net/wireless`.
 
-pub fn get_network_info() -> Vec<(String, String)> {
-    let mut networks = Vec::new();
+use super::netlink;
+use super::runner::CommandRunner;
+use super::sysroot::Sysroot;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
 
-    if let Some(output) = run_cmd("ip", &["-4", "addr", "show", "scope", "global"]) {
-        let ip_output = String::from_utf8_lossy(&output.stdout);
-        let mut current_iface = String::new();
+/// What kind of link an interface is, from its sysfs attributes rather than
+/// its name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    Ethernet,
+    Wifi,
+    Bridge,
+    Bond,
+    Vlan,
+    WireGuard,
+    /// tun or tap, as used by OpenVPN, Tailscale and ZeroTier.
+    Tun,
+    /// Other software Ethernet devices: veth pairs, macvlan, dummy.
+    Virtual,
+    Other,
+}
+
+impl LinkKind {
+    fn name(self) -> &'static str {
+        match self {
+            LinkKind::Ethernet => "Ethernet",
+            LinkKind::Wifi => "Wi-Fi",
+            LinkKind::Bridge => "bridge",
+            LinkKind::Bond => "bond",
+            LinkKind::Vlan => "VLAN",
+            LinkKind::WireGuard => "WireGuard",
+            LinkKind::Tun => "tun",
+            LinkKind::Virtual => "virtual",
+            LinkKind::Other => "other",
+        }
+    }
+}
+
+/// The association of a Wi-Fi interface.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct WifiInfo {
+    pub ssid: Option<String>,
+    pub signal_dbm: Option<i32>,
+    pub frequency_mhz: Option<u32>,
+    /// Transmit bitrate.
+    pub bitrate_mbps: Option<f64>,
+}
+
+impl WifiInfo {
+    /// `2.4 GHz`, `5 GHz` or `6 GHz`.
+    pub fn band(&self) -> Option<&'static str> {
+        match self.frequency_mhz? {
+            2400..=2500 => Some("2.4 GHz"),
+            5150..=5900 => Some("5 GHz"),
+            5925..=7125 => Some("6 GHz"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WifiInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        parts.extend(self.ssid.as_ref().map(|ssid| format!("\"{}\"", ssid)));
+        parts.extend(self.band().map(str::to_string));
+        parts.extend(self.signal_dbm.map(|dbm| format!("{} dBm", dbm)));
+        parts.extend(self.bitrate_mbps.map(|rate| format!("{:.0} Mbit/s", rate)));
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// One network interface from `/sys/class/net`.
+#[derive(Clone, Debug, Serialize)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub mac: Option<String>,
+    pub mtu: Option<u32>,
+    /// `operstate`: `up`, `down`, `dormant` or `unknown`.
+    pub state: Option<String>,
+    pub kind: LinkKind,
+    /// Whether the link has carrier; unknown while the interface is down.
+    pub carrier: Option<bool>,
+    /// Negotiated speed, for Ethernet and bonds.
+    pub speed_mbps: Option<u32>,
+    /// The bridge or bond this interface is enslaved to.
+    pub master: Option<String>,
+    /// Association details, filled in by [`fill_wifi_details`].
+    pub wifi: Option<WifiInfo>,
+    /// IPv4 addresses in CIDR notation.
+    pub ipv4: Vec<String>,
+    /// Global IPv6 addresses in CIDR notation; link-local ones are left out.
+    pub ipv6: Vec<String>,
+    /// Gateway of the default route through this interface.
+    pub ipv4_gateway: Option<String>,
+    pub ipv6_gateway: Option<String>,
+    #[serde(skip)]
+    index: u32,
+}
+
+impl InterfaceInfo {
+    fn is_configured(&self) -> bool {
+        !self.ipv4.is_empty() || !self.ipv6.is_empty()
+    }
+
+    fn is_up(&self) -> bool {
+        self.state.as_deref() == Some("up")
+    }
+}
+
+impl fmt::Display for InterfaceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let addresses: Vec<&str> = self
+            .ipv4
+            .iter()
+            .chain(&self.ipv6)
+            .map(String::as_str)
+            .collect();
+        write!(f, "{}", self.name)?;
+        if !addresses.is_empty() {
+            write!(f, ": {}", addresses.join(", "))?;
+        }
+
+        let gateways: Vec<&str> = self
+            .ipv4_gateway
+            .iter()
+            .chain(&self.ipv6_gateway)
+            .map(String::as_str)
+            .collect();
+        let mut extras = vec![self.kind.name().to_string()];
+        extras.extend(self.speed_mbps.map(format_speed));
+        if let Some(ref wifi) = self.wifi {
+            let wifi = wifi.to_string();
+            if !wifi.is_empty() {
+                extras.push(wifi);
+            }
+        }
+        if self.carrier == Some(false) {
+            extras.push("no carrier".to_string());
+        }
+        if let Some(ref master) = self.master {
+            extras.push(format!("in {}", master));
+        }
+        if !gateways.is_empty() {
+            extras.push(format!("via {}", gateways.join(" / ")));
+        }
+        if let Some(mtu) = self.mtu {
+            extras.push(format!("MTU {}", mtu));
+        }
+        extras.extend(self.mac.clone());
+        write!(f, " ({})", extras.join(", "))
+    }
+}
+
+fn format_speed(mbps: u32) -> String {
+    if mbps >= 1000 {
+        let gbps = format!("{:.1}", mbps as f64 / 1000.0);
+        format!("{} Gb/s", gbps.trim_end_matches(".0"))
+    } else {
+        format!("{} Mb/s", mbps)
+    }
+}
+
+/// Interfaces plus the resolver configuration.
+#[derive(Clone, Debug, Serialize)]
+pub struct NetworkInfo {
+    /// Every interface except loopback, in ifindex order.
+    pub interfaces: Vec<InterfaceInfo>,
+    pub dns_servers: Vec<String>,
+    pub search_domains: Vec<String>,
+    /// `systemd-resolved` when `/etc/resolv.conf` points at its stub
+    /// listener; `dns_servers` are then its upstream servers.
+    pub resolver: Option<String>,
+}
+
+impl NetworkInfo {
+    /// Interfaces that are up or have an address, as shown in the text
+    /// output.
+    pub fn active(&self) -> impl Iterator<Item = &InterfaceInfo> {
+        self.interfaces
+            .iter()
+            .filter(|iface| iface.is_configured() || iface.is_up())
+    }
+
+    /// `DNS 192.168.1.1, fd00::1 (systemd-resolved, search home.arpa)`
+    pub fn dns_summary(&self) -> Option<String> {
+        if self.dns_servers.is_empty() {
+            return None;
+        }
+        let mut summary = format!("DNS {}", self.dns_servers.join(", "));
+        let mut extras: Vec<String> = self.resolver.iter().cloned().collect();
+        if !self.search_domains.is_empty() {
+            extras.push(format!("search {}", self.search_domains.join(" ")));
+        }
+        if !extras.is_empty() {
+            summary.push_str(&format!(" ({})", extras.join(", ")));
+        }
+        Some(summary)
+    }
+}
+
+/// Interfaces with their addresses and default gateways, and DNS servers.
+pub fn get_network(root: &Sysroot) -> NetworkInfo {
+    let mut interfaces = read_interfaces(root);
+    let (addresses, routes) = live_addresses_and_routes(root, &interfaces)
+        .unwrap_or_else(|| (proc_addresses(root), proc_default_routes(root)));
+    let by_name: HashMap<String, usize> = interfaces
+        .iter()
+        .enumerate()
+        .map(|(i, iface)| (iface.name.clone(), i))
+        .collect();
+
+    for (name, addr, prefix) in addresses {
+        let Some(&i) = by_name.get(&name) else {
+            continue;
+        };
+        let cidr = format!("{}/{}", addr, prefix);
+        let list = match addr {
+            IpAddr::V4(_) => &mut interfaces[i].ipv4,
+            IpAddr::V6(_) => &mut interfaces[i].ipv6,
+        };
+        if !list.contains(&cidr) {
+            list.push(cidr);
+        }
+    }
+    for (name, gateway) in routes {
+        let Some(&i) = by_name.get(&name) else {
+            continue;
+        };
+        let slot = match gateway {
+            IpAddr::V4(_) => &mut interfaces[i].ipv4_gateway,
+            IpAddr::V6(_) => &mut interfaces[i].ipv6_gateway,
+        };
+        slot.get_or_insert_with(|| gateway.to_string());
+    }
+
+    let (dns_servers, search_domains, resolver) = read_dns(root);
+    NetworkInfo {
+        interfaces,
+        dns_servers,
+        search_domains,
+        resolver,
+    }
+}
+
+/// `(interface, address, prefix length)`.
+type Addresses = Vec<(String, IpAddr, u8)>;
+/// `(interface, default gateway)`.
+type Routes = Vec<(String, IpAddr)>;
+
+/// Global addresses and default gateways from rtnetlink. Only used for the
+/// real root, since netlink always describes this machine.
+fn live_addresses_and_routes(
+    root: &Sysroot,
+    interfaces: &[InterfaceInfo],
+) -> Option<(Addresses, Routes)> {
+    if !root.is_host() {
+        return None;
+    }
+    let addresses = netlink::dump_addresses().ok()?;
+    let routes = netlink::dump_default_routes().ok()?;
+    let name = |index: u32| {
+        interfaces
+            .iter()
+            .find(|iface| iface.index == index)
+            .map(|iface| iface.name.clone())
+    };
+    Some((
+        addresses
+            .into_iter()
+            .filter(|a| a.scope == libc::RT_SCOPE_UNIVERSE && is_reportable(&a.addr))
+            .filter_map(|a| Some((name(a.index)?, a.addr, a.prefix_len)))
+            .collect(),
+        routes
+            .into_iter()
+            .filter_map(|r| Some((name(r.index)?, r.gateway)))
+            .collect(),
+    ))
+}
+
+fn is_reportable(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(v4) => !v4.is_loopback(),
+        IpAddr::V6(v6) => !v6.is_loopback() && !v6.is_unicast_link_local(),
+    }
+}
+
+/// Non-loopback interfaces from `/sys/class/net`, in ifindex order.
+fn read_interfaces(root: &Sysroot) -> Vec<InterfaceInfo> {
+    let Ok(entries) = root.read_dir("/sys/class/net") else {
+        return Vec::new();
+    };
+    let mut interfaces: Vec<InterfaceInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name == "lo" {
+                return None;
+            }
+            Some(InterfaceInfo {
+                index: read_value(&dir, "ifindex")?.parse().ok()?,
+                mac: read_value(&dir, "address").filter(|mac| mac != "00:00:00:00:00:00"),
+                mtu: read_value(&dir, "mtu").and_then(|mtu| mtu.parse().ok()),
+                state: read_value(&dir, "operstate"),
+                kind: link_kind(&dir),
+                // Both read as EINVAL while the interface is down
+                carrier: read_value(&dir, "carrier").map(|carrier| carrier == "1"),
+                speed_mbps: read_value(&dir, "speed")
+                    .and_then(|speed| speed.parse::<i64>().ok())
+                    .filter(|&speed| speed > 0)
+                    .map(|speed| speed as u32),
+                master: fs::read_link(dir.join("master"))
+                    .ok()
+                    .and_then(|target| Some(target.file_name()?.to_string_lossy().into_owned())),
+                wifi: None,
+                name,
+                ipv4: Vec::new(),
+                ipv6: Vec::new(),
+                ipv4_gateway: None,
+                ipv6_gateway: None,
+            })
+        })
+        .collect();
+    interfaces.sort_by_key(|iface| iface.index);
+    interfaces
+}
+
+/// Classify an interface from the marker directories and files its driver
+/// creates in sysfs, and the `DEVTYPE` in its uevent.
+fn link_kind(dir: &Path) -> LinkKind {
+    let devtype = read_value(dir, "uevent").and_then(|uevent| {
+        uevent
+            .lines()
+            .find_map(|line| line.strip_prefix("DEVTYPE=").map(str::to_string))
+    });
+    let has = |file: &str| dir.join(file).exists();
+
+    if has("wireless") || has("phy80211") || devtype.as_deref() == Some("wlan") {
+        LinkKind::Wifi
+    } else if has("bridge") {
+        LinkKind::Bridge
+    } else if has("bonding") {
+        LinkKind::Bond
+    } else if devtype.as_deref() == Some("vlan") {
+        LinkKind::Vlan
+    } else if devtype.as_deref() == Some("wireguard") {
+        LinkKind::WireGuard
+    } else if has("tun_flags") {
+        LinkKind::Tun
+    } else if read_value(dir, "type").as_deref() == Some("1") {
+        // ARPHRD_ETHER: physical NICs have a backing device
+        if has("device") {
+            LinkKind::Ethernet
+        } else {
+            LinkKind::Virtual
+        }
+    } else {
+        LinkKind::Other
+    }
+}
+
+/// Add SSID, band, signal and bitrate to Wi-Fi interfaces that are up.
+///
+/// `iw dev <name> link` reports the association over nl80211. Without `iw`,
+/// `/proc/net/wireless` still gives the signal level.
+pub fn fill_wifi_details(root: &Sysroot, cmd: &dyn CommandRunner, network: &mut NetworkInfo) {
+    let proc_signal = root
+        .read_to_string("/proc/net/wireless")
+        .map(|content| parse_proc_wireless(&content))
+        .unwrap_or_default();
 
-        for line in ip_output.lines() {
-            // Interface line: "2: enp6s0: <BROADCAST..."
-            if !line.starts_with(' ')
-                && line.contains(':')
-                && let Some(iface) = line.split(':').nth(1)
+    for iface in &mut network.interfaces {
+        if iface.kind != LinkKind::Wifi || !iface.is_up() {
+            continue;
+        }
+        let mut wifi = cmd
+            .run("iw", &["dev", &iface.name, "link"])
+            .and_then(|output| parse_iw_link(&output))
+            .unwrap_or_default();
+        if wifi.signal_dbm.is_none() {
+            wifi.signal_dbm = proc_signal
+                .iter()
+                .find(|(name, _)| *name == iface.name)
+                .map(|(_, dbm)| *dbm);
+        }
+        iface.wifi = Some(wifi);
+    }
+}
+
+/// `iw dev <name> link` output. `None` when not connected.
+fn parse_iw_link(output: &str) -> Option<WifiInfo> {
+    if !output.starts_with("Connected to") {
+        return None;
+    }
+    let mut wifi = WifiInfo::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.trim().split_once(": ") else {
+            continue;
+        };
+        let number = || value.split_whitespace().next()?.parse::<f64>().ok();
+        match key {
+            "SSID" => wifi.ssid = Some(value.to_string()),
+            "freq" => wifi.frequency_mhz = number().map(|mhz| mhz as u32),
+            "signal" => wifi.signal_dbm = number().map(|dbm| dbm as i32),
+            "tx bitrate" => wifi.bitrate_mbps = number(),
+            _ => {}
+        }
+    }
+    Some(wifi)
+}
+
+/// `(interface, signal dBm)` from `/proc/net/wireless`. Drivers report the
+/// level either in dBm or as an unsigned byte offset by 256.
+fn parse_proc_wireless(content: &str) -> Vec<(String, i32)> {
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let level: f64 = rest
+                .split_whitespace()
+                .nth(2)?
+                .trim_end_matches('.')
+                .parse()
+                .ok()?;
+            let level = level as i32;
+            let dbm = if level > 0 { level - 256 } else { level };
+            Some((name.trim().to_string(), dbm))
+        })
+        .collect()
+}
+
+/// `(interface, address, prefix length)` from `/proc/net/fib_trie` and
+/// `/proc/net/if_inet6`.
+///
+/// fib_trie lists local IPv4 addresses but not their interface; that comes
+/// from the most specific subnet route in `/proc/net/route` containing them.
+fn proc_addresses(root: &Sysroot) -> Addresses {
+    let mut addresses = Vec::new();
+
+    let routes = proc_ipv4_routes(root);
+    if let Ok(fib_trie) = root.read_to_string("/proc/net/fib_trie") {
+        for local in parse_fib_trie_locals(&fib_trie) {
+            let bits = u32::from(local);
+            let subnet = routes
+                .iter()
+                .filter(|route| route.mask != 0 && bits & route.mask == route.destination)
+                .max_by_key(|route| route.mask.count_ones());
+            if let Some(route) = subnet {
+                let prefix = route.mask.count_ones() as u8;
+                addresses.push((route.iface.clone(), IpAddr::V4(local), prefix));
+            }
+        }
+    }
+
+    if let Ok(if_inet6) = root.read_to_string("/proc/net/if_inet6") {
+        for line in if_inet6.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if let [addr, _index, prefix, scope, _flags, name] = fields[..]
+                && scope == "00"
+                && let Some(addr) = parse_ipv6_hex(addr)
+                && let Ok(prefix) = u8::from_str_radix(prefix, 16)
+            {
+                addresses.push((name.to_string(), IpAddr::V6(addr), prefix));
+            }
+        }
+    }
+
+    addresses
+}
+
+/// Addresses with a `/32 host LOCAL` leaf in `/proc/net/fib_trie`, without
+/// loopback and in first-seen order. The file repeats them per table.
+fn parse_fib_trie_locals(content: &str) -> Vec<Ipv4Addr> {
+    let mut locals = Vec::new();
+    let mut last_leaf = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(addr) = line.strip_prefix("|-- ") {
+            last_leaf = addr.parse::<Ipv4Addr>().ok();
+        } else if line.starts_with("/32 host LOCAL")
+            && let Some(addr) = last_leaf
+            && !addr.is_loopback()
+            && !locals.contains(&addr)
+        {
+            locals.push(addr);
+        }
+    }
+    locals
+}
+
+struct ProcRoute {
+    iface: String,
+    destination: u32,
+    gateway: u32,
+    mask: u32,
+}
+
+/// `/proc/net/route`, with addresses converted to host-order integers.
+fn proc_ipv4_routes(root: &Sysroot) -> Vec<ProcRoute> {
+    let Ok(content) = root.read_to_string("/proc/net/route") else {
+        return Vec::new();
+    };
+    // The kernel prints each address's in-memory bytes as one native-endian
+    // hex word, so native byte order gets the address bytes back
+    let hex = |field: &str| {
+        u32::from_str_radix(field, 16)
+            .ok()
+            .map(|v| u32::from(Ipv4Addr::from(v.to_ne_bytes())))
+    };
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some(ProcRoute {
+                iface: fields.first()?.to_string(),
+                destination: hex(fields.get(1)?)?,
+                gateway: hex(fields.get(2)?)?,
+                mask: hex(fields.get(7)?)?,
+            })
+        })
+        .collect()
+}
+
+/// `(interface, gateway)` for default routes in `/proc/net/route` and
+/// `/proc/net/ipv6_route`.
+fn proc_default_routes(root: &Sysroot) -> Routes {
+    let mut routes: Routes = proc_ipv4_routes(root)
+        .into_iter()
+        .filter(|route| route.destination == 0 && route.mask == 0 && route.gateway != 0)
+        .map(|route| (route.iface, IpAddr::V4(Ipv4Addr::from(route.gateway))))
+        .collect();
+
+    if let Ok(content) = root.read_to_string("/proc/net/ipv6_route") {
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if let [dest, dest_len, _, _, next_hop, _, _, _, _, name] = fields[..]
+                && dest_len == "00"
+                && parse_ipv6_hex(dest) == Some(Ipv6Addr::UNSPECIFIED)
+                && let Some(gateway) = parse_ipv6_hex(next_hop)
+                && !gateway.is_unspecified()
             {
-                current_iface = iface.trim().to_string();
+                routes.push((name.to_string(), IpAddr::V6(gateway)));
             }
-            // IP line: "    inet 10.0.0.21/24 ..."
-            if line.trim().starts_with("inet ") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    let ip = parts[1].to_string();
-                    networks.push((current_iface.clone(), ip));
-                }
+        }
+    }
+    routes
+}
+
+/// 32 hex digits, as in `/proc/net/if_inet6` and `/proc/net/ipv6_route`.
+fn parse_ipv6_hex(hex: &str) -> Option<Ipv6Addr> {
+    if hex.len() != 32 {
+        return None;
+    }
+    u128::from_str_radix(hex, 16).ok().map(Ipv6Addr::from)
+}
+
+/// Nameservers, search domains and the stub resolver in use.
+fn read_dns(root: &Sysroot) -> (Vec<String>, Vec<String>, Option<String>) {
+    let Ok(content) = root.read_to_string("/etc/resolv.conf") else {
+        return (Vec::new(), Vec::new(), None);
+    };
+    let (servers, search) = parse_resolv_conf(&content);
+
+    // systemd-resolved's stub listeners; the real servers are in its own copy
+    let is_stub = |server: &String| server == "127.0.0.53" || server == "127.0.0.54";
+    if !servers.is_empty() && servers.iter().all(is_stub) {
+        let upstream = root
+            .read_to_string("/run/systemd/resolve/resolv.conf")
+            .map(|content| parse_resolv_conf(&content))
+            .ok()
+            .filter(|(servers, _)| !servers.is_empty());
+        let (servers, upstream_search) = upstream.unwrap_or((servers, Vec::new()));
+        let search = if search.is_empty() {
+            upstream_search
+        } else {
+            search
+        };
+        return (servers, search, Some("systemd-resolved".to_string()));
+    }
+    (servers, search, None)
+}
+
+fn parse_resolv_conf(content: &str) -> (Vec<String>, Vec<String>) {
+    let mut servers = Vec::new();
+    let mut search = Vec::new();
+    for line in content.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("nameserver") => servers.extend(words.next().map(str::to_string)),
+            // The last search or domain line wins; "." means none
+            Some("search" | "domain") => {
+                search = words
+                    .filter(|domain| *domain != ".")
+                    .map(str::to_string)
+                    .collect()
             }
+            _ => {}
         }
     }
+    (servers, search)
+}
 
-    networks
+fn read_value(dir: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(dir.join(file))
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
 }
 
-pub fn get_local_ip() -> String {
-    let networks = get_network_info();
+/// The machine's LAN address: an uplink bridge together with the primary
+/// interface, else a bond, else the first physical interface.
+///
+/// VPN and overlay links (WireGuard, tun/tap) are left to the VPN module.
+/// Container and VM plumbing (veth pairs, and bridges like `docker0` or
+/// `virbr0` with no physical port) is only used when nothing else has an
+/// address, as inside a container.
+pub fn get_local_ip(root: &Sysroot) -> String {
+    let interfaces = get_network(root).interfaces;
+    let is_uplink_bridge = |bridge: &InterfaceInfo| {
+        interfaces.iter().any(|port| {
+            port.master.as_deref() == Some(bridge.name.as_str())
+                && matches!(
+                    port.kind,
+                    LinkKind::Ethernet | LinkKind::Wifi | LinkKind::Bond | LinkKind::Vlan
+                )
+        })
+    };
+    let networks: Vec<(&str, &str, bool, LinkKind)> = interfaces
+        .iter()
+        .filter(|iface| !matches!(iface.kind, LinkKind::WireGuard | LinkKind::Tun))
+        .flat_map(|iface| {
+            let is_virtual = match iface.kind {
+                LinkKind::Virtual => true,
+                LinkKind::Bridge => !is_uplink_bridge(iface),
+                _ => false,
+            };
+            iface
+                .ipv4
+                .iter()
+                .map(move |ip| (iface.name.as_str(), ip.as_str(), is_virtual, iface.kind))
+        })
+        .collect();
 
     if networks.is_empty() {
         return "Unknown".to_string();
     }
 
-    // Prioritize bridges (br0, br1, etc.) and bonds, then regular interfaces
-    let bridge = networks.iter().find(|(iface, _)| iface.starts_with("br"));
-    let bond = networks.iter().find(|(iface, _)| iface.starts_with("bond"));
+    // Prioritize bridges and bonds, then regular interfaces
+    let bridge = networks
+        .iter()
+        .find(|(_, _, is_virtual, kind)| *kind == LinkKind::Bridge && !is_virtual);
+    let bond = networks
+        .iter()
+        .find(|(_, _, _, kind)| *kind == LinkKind::Bond);
 
     // If we have a bridge, show it prominently
-    if let Some((iface, ip)) = bridge {
+    if let Some((iface, ip, _, _)) = bridge {
         // Also find primary non-bridge interface
-        let primary = networks.iter().find(|(i, _)| {
-            !i.starts_with("br")
-                && !i.starts_with("bond")
-                && !i.starts_with("veth")
-                && !i.starts_with("docker")
-                && !i.starts_with("virbr")
+        let primary = networks.iter().find(|(_, _, is_virtual, kind)| {
+            !matches!(kind, LinkKind::Bridge | LinkKind::Bond) && !is_virtual
         });
 
-        if let Some((p_iface, p_ip)) = primary {
+        if let Some((p_iface, p_ip, _, _)) = primary {
             return format!("{} ({}), {} ({})", ip, iface, p_ip, p_iface);
         }
         return format!("{} ({})", ip, iface);
     }
 
     // If we have a bond, show it
-    if let Some((iface, ip)) = bond {
+    if let Some((iface, ip, _, _)) = bond {
         return format!("{} ({})", ip, iface);
     }
 
     // Regular interface - skip virtual ones
-    for (iface, ip) in &networks {
-        if !iface.starts_with("veth") && !iface.starts_with("docker") && !iface.starts_with("virbr")
-        {
+    for (_, ip, is_virtual, _) in &networks {
+        if !is_virtual {
             return ip.to_string();
         }
     }
@@ -73,6 +696,130 @@ pub fn get_local_ip() -> String {
     // Fallback to first
     networks
         .first()
-        .map(|(_, ip)| ip.clone())
+        .map(|(_, ip, _, _)| ip.to_string())
         .unwrap_or_else(|| "Unknown".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+
+    #[test]
+    fn test_network_from_proc() {
+        let network = get_network(&fixtures::root("arch-desktop"));
+        let names: Vec<&str> = network.interfaces.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["wlan0", "enp6s0", "br0", "enp7s0", "docker0", "wg0"]
+        );
+
+        let lines: Vec<String> = network.active().map(|i| i.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "enp6s0 (Ethernet, 2.5 Gb/s, in br0, MTU 1500, 3c:7c:3f:a1:b2:c4)",
+                "br0: 10.0.0.21/24, 2001:db8:21::21/64 (bridge, via 10.0.0.1 / fe80::1, MTU 1500, 3c:7c:3f:a1:b2:c4)",
+                "enp7s0: 10.10.0.21/24 (Ethernet, 10 Gb/s, MTU 9000, 3c:7c:3f:a1:b2:c5)",
+                "docker0: 172.17.0.1/16 (bridge, no carrier, MTU 1500, 02:42:5c:9e:31:7a)",
+                "wg0: 10.8.0.2/24 (WireGuard, MTU 1420)",
+            ]
+        );
+        assert_eq!(network.interfaces[0].kind, LinkKind::Wifi);
+        // Link-local addresses alone don't make an interface configured
+        assert!(network.interfaces[1].ipv6.is_empty());
+        assert_eq!(network.interfaces[0].state.as_deref(), Some("down"));
+        assert_eq!(
+            network.dns_summary().as_deref(),
+            Some("DNS 10.0.0.1 (search lan)")
+        );
+    }
+
+    #[test]
+    fn test_wifi_details_from_iw() {
+        let machine = "ubuntu-thinkpad";
+        let root = fixtures::root(machine);
+        let mut network = get_network(&root);
+        fill_wifi_details(&root, &fixtures::commands(machine), &mut network);
+
+        let wifi = network.interfaces[1].wifi.as_ref().unwrap();
+        assert_eq!(wifi.ssid.as_deref(), Some("Lindqvist 5G"));
+        assert_eq!(wifi.frequency_mhz, Some(5500));
+        assert_eq!(
+            network.interfaces[1].to_string(),
+            "wlp0s20f3: 192.168.1.42/24 (Wi-Fi, \"Lindqvist 5G\" 5 GHz -52 dBm 780 Mbit/s, via 192.168.1.1, MTU 1500, f4:4e:e3:91:c2:5a)"
+        );
+        // The unplugged Ethernet port is down: no carrier or speed to read
+        let ethernet = &network.interfaces[0];
+        assert_eq!(ethernet.kind, LinkKind::Ethernet);
+        assert_eq!((ethernet.carrier, ethernet.speed_mbps), (None, None));
+        assert!(ethernet.wifi.is_none());
+    }
+
+    #[test]
+    fn test_wifi_signal_without_iw() {
+        // No iw recorded: only the signal, from /proc/net/wireless, where this
+        // driver reports the level as an unsigned byte
+        let machine = "nixos-framework";
+        let root = fixtures::root(machine);
+        let mut network = get_network(&root);
+        fill_wifi_details(&root, &fixtures::commands(machine), &mut network);
+        let wifi = network.interfaces[0].wifi.as_ref().unwrap();
+        assert_eq!(wifi.signal_dbm, Some(-63));
+        assert_eq!(wifi.to_string(), "-63 dBm");
+    }
+
+    #[test]
+    fn test_parse_iw_link_disconnected() {
+        assert!(parse_iw_link("Not connected.\n").is_none());
+    }
+
+    #[test]
+    fn test_dns_behind_systemd_resolved() {
+        let network = get_network(&fixtures::root("ubuntu-thinkpad"));
+        assert_eq!(network.resolver.as_deref(), Some("systemd-resolved"));
+        assert_eq!(
+            network.dns_summary().as_deref(),
+            Some("DNS 192.168.1.1, fd00::1 (systemd-resolved, search home.arpa)")
+        );
+        // "search ." is the root domain, i.e. no search list
+        let fedora = get_network(&fixtures::root("fedora-vm"));
+        assert_eq!(fedora.dns_servers, ["192.168.122.1"]);
+        assert!(fedora.search_domains.is_empty());
+    }
+
+    #[test]
+    fn test_parse_fib_trie_skips_loopback_and_duplicates() {
+        let trie = "Main:\n  +-- 0.0.0.0/0 3 0 5\n     |-- 127.0.0.1\n        /32 host LOCAL\n     |-- 192.168.1.42\n        /32 host LOCAL\n     |-- 192.168.1.255\n        /32 link BROADCAST\nLocal:\n     |-- 192.168.1.42\n        /32 host LOCAL\n";
+        assert_eq!(
+            parse_fib_trie_locals(trie),
+            [Ipv4Addr::new(192, 168, 1, 42)]
+        );
+    }
+
+    #[test]
+    fn test_local_ip_prefers_bridge() {
+        assert_eq!(
+            get_local_ip(&fixtures::root("arch-desktop")),
+            "10.0.0.21/24 (br0), 10.10.0.21/24 (enp7s0)"
+        );
+        assert_eq!(
+            get_local_ip(&fixtures::root("ubuntu-thinkpad")),
+            "192.168.1.42/24"
+        );
+    }
+
+    #[test]
+    fn test_local_ip_skips_overlays() {
+        // ZeroTier's tap comes first but isn't the LAN address
+        assert_eq!(
+            get_local_ip(&fixtures::root("void-laptop")),
+            "192.168.0.113/24"
+        );
+        // Inside a container the veth is all there is
+        assert_eq!(
+            get_local_ip(&fixtures::root("alpine-docker")),
+            "172.17.0.2/16"
+        );
+    }
+}
@@ -0,0 +1,731 @@
+//! Package counts, read straight from each package manager's database.
+//!
+//! Spawning `pacman -Qq` or `rpm -qa` is among the slowest things ghostfetch
+//! does, so every counter reads the on-disk database first and only falls back
+//! to the command when that database exists but cannot be read. The one
+//! exception is a nix system closure, which only `nix-store` can walk.
+
+use super::runner::CommandRunner;
+use super::sysroot::Sysroot;
+use std::fs;
+use std::path::Path;
+
+/// A system package manager whose database marks it as the distro's own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NativeManager {
+    Pacman,
+    Dpkg,
+    Rpm,
+}
+
+impl NativeManager {
+    pub const ALL: [NativeManager; 3] = [
+        NativeManager::Pacman,
+        NativeManager::Dpkg,
+        NativeManager::Rpm,
+    ];
+
+    /// The database whose presence means the manager is in use.
+    pub fn database(self) -> &'static str {
+        match self {
+            NativeManager::Pacman => "/var/lib/pacman/local",
+            NativeManager::Dpkg => "/var/lib/dpkg/status",
+            NativeManager::Rpm => "/var/lib/rpm",
+        }
+    }
+
+    pub fn is_present(self, root: &Sysroot) -> bool {
+        root.path(self.database()).exists()
+    }
+}
+
+/// Native package managers present under `root`. Hybrid systems can have
+/// more than one.
+pub fn native_managers(root: &Sysroot) -> Vec<NativeManager> {
+    NativeManager::ALL
+        .into_iter()
+        .filter(|manager| manager.is_present(root))
+        .collect()
+}
+
+/// Per-user locations (user flatpaks, nix profiles, AppImages, cargo and
+/// pipx installs) are looked up under `home`.
+pub fn get_packages(root: &Sysroot, home: Option<&Path>, cmd: &dyn CommandRunner) -> String {
+    let mut counts = Vec::new();
+    let mut add = |name: &str, count: Option<usize>| {
+        if let Some(count) = count
+            && count > 0
+        {
+            counts.push(format!("{} ({})", count, name));
+        }
+    };
+
+    // Native package managers are counted independently; hybrid systems
+    // (e.g. dpkg inside an Arch install for some tooling) report all of them.
+    add("pacman", count_pacman(root, cmd));
+    add("dpkg", count_dpkg(root, cmd));
+    add("rpm", count_rpm(root, cmd));
+    add("apk", count_apk(root, cmd));
+    add("xbps", count_xbps(root, cmd));
+    add("portage", count_portage(root));
+    add(
+        "nix-system",
+        count_nix_closure(root, "/run/current-system", cmd),
+    );
+    add(
+        "nix-default",
+        count_nix_closure(root, "/nix/var/nix/profiles/default", cmd),
+    );
+    add("nix-user", count_nix_user(home, cmd));
+    add("brew", count_brew(root, home));
+    add("flatpak", count_flatpak(root, home, cmd));
+    add("snap", count_snap(root, cmd));
+    add("appimage", count_appimages(home));
+    add("cargo", count_cargo(home));
+    add("pipx", count_pipx(home));
+
+    if counts.is_empty() {
+        "Unknown".to_string()
+    } else {
+        counts.join(", ")
+    }
+}
+
+/// One directory per installed package in `/var/lib/pacman/local`.
+fn count_pacman(root: &Sysroot, cmd: &dyn CommandRunner) -> Option<usize> {
+    if !NativeManager::Pacman.is_present(root) {
+        return None;
+    }
+    count_dirs(&root.path(NativeManager::Pacman.database()))
+        .or_else(|| count_lines(cmd.run("pacman", &["-Qq"])))
+}
+
+/// Installed stanzas in `/var/lib/dpkg/status`.
+fn count_dpkg(root: &Sysroot, cmd: &dyn CommandRunner) -> Option<usize> {
+    if !NativeManager::Dpkg.is_present(root) {
+        return None;
+    }
+    match fs::read_to_string(root.path(NativeManager::Dpkg.database())) {
+        Ok(content) => Some(count_dpkg_status(&content)),
+        Err(_) => count_lines(cmd.run("dpkg-query", &["-f", ".\n", "-W"])),
+    }
+}
+
+/// Count packages whose status is `install ok installed`, skipping ones that
+/// were removed but still have config files (`deinstall ok config-files`).
+fn count_dpkg_status(content: &str) -> usize {
+    content
+        .lines()
+        .filter(|line| {
+            line.strip_prefix("Status:")
+                .is_some_and(|status| status.trim_end().ends_with(" installed"))
+        })
+        .count()
+}
+
+/// Rows in the rpm database: `rpmdb.sqlite` on Fedora/RHEL, `Packages.db`
+/// (ndb) on openSUSE. The old Berkeley DB format is left to `rpm -qa`.
+fn count_rpm(root: &Sysroot, cmd: &dyn CommandRunner) -> Option<usize> {
+    if !NativeManager::Rpm.is_present(root) {
+        return None;
+    }
+    let dir = root.path(NativeManager::Rpm.database());
+
+    let sqlite = dir.join("rpmdb.sqlite");
+    let ndb = dir.join("Packages.db");
+    let direct = if sqlite.exists() {
+        read_sqlite_with_wal(&sqlite).and_then(|db| count_sqlite_rows(&db, "Packages"))
+    } else if ndb.exists() {
+        fs::read(&ndb).ok().and_then(|db| count_ndb_packages(&db))
+    } else {
+        None
+    };
+
+    direct.or_else(|| count_lines(cmd.run("rpm", &["-qa"])))
+}
+
+/// One directory per installed app, system-wide and per user.
+fn count_flatpak(root: &Sysroot, home: Option<&Path>, cmd: &dyn CommandRunner) -> Option<usize> {
+    let mut dirs = vec![root.path("/var/lib/flatpak/app")];
+    if let Some(home) = home {
+        dirs.push(home.join(".local/share/flatpak/app"));
+    }
+    let dirs: Vec<_> = dirs.into_iter().filter(|dir| dir.exists()).collect();
+    if dirs.is_empty() {
+        return None;
+    }
+
+    dirs.iter()
+        .map(|dir| count_dirs(dir))
+        .sum::<Option<usize>>()
+        .or_else(|| count_lines(cmd.run("flatpak", &["list", "--app"])))
+}
+
+/// Each installed snap is mounted under `/snap/<name>/<revision>`, with a
+/// `current` symlink pointing at the active revision.
+fn count_snap(root: &Sysroot, cmd: &dyn CommandRunner) -> Option<usize> {
+    let snap = root.path("/snap");
+    if !snap.exists() {
+        return None;
+    }
+
+    match fs::read_dir(&snap) {
+        Ok(entries) => Some(
+            entries
+                .flatten()
+                .filter(|entry| fs::symlink_metadata(entry.path().join("current")).is_ok())
+                .count(),
+        ),
+        // The header line isn't a package
+        Err(_) => count_lines(cmd.run("snap", &["list"])).map(|n| n.saturating_sub(1)),
+    }
+}
+
+/// One `P:` (package name) line per package in `/lib/apk/db/installed`.
+fn count_apk(root: &Sysroot, cmd: &dyn CommandRunner) -> Option<usize> {
+    let installed = root.path("/lib/apk/db/installed");
+    if !installed.exists() {
+        return None;
+    }
+    match fs::read_to_string(&installed) {
+        Ok(content) => Some(content.lines().filter(|l| l.starts_with("P:")).count()),
+        Err(_) => count_lines(cmd.run("apk", &["info"])),
+    }
+}
+
+/// Installed entries in `/var/db/xbps/pkgdb-<format>.plist`.
+fn count_xbps(root: &Sysroot, cmd: &dyn CommandRunner) -> Option<usize> {
+    let pkgdb = fs::read_dir(root.path("/var/db/xbps"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("pkgdb-") && name.ends_with(".plist"))
+        })?;
+    match fs::read_to_string(&pkgdb) {
+        Ok(content) => Some(count_xbps_pkgdb(&content)),
+        Err(_) => count_lines(cmd.run("xbps-query", &["-l"])),
+    }
+}
+
+/// Every package dictionary in the pkgdb plist has a `state` key; only
+/// `installed` ones count (not `unpacked` or `half-removed`).
+fn count_xbps_pkgdb(content: &str) -> usize {
+    let mut lines = content.lines().map(str::trim);
+    let mut count = 0;
+    while let Some(line) = lines.next() {
+        if line == "<key>state</key>" && lines.next() == Some("<string>installed</string>") {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// One `/var/db/pkg/<category>/<package>-<version>` directory per package.
+fn count_portage(root: &Sysroot) -> Option<usize> {
+    let categories = fs::read_dir(root.path("/var/db/pkg")).ok()?;
+    Some(
+        categories
+            .flatten()
+            .filter_map(|category| count_dirs(&category.path()))
+            .sum(),
+    )
+}
+
+/// Store paths in the closure of a system-wide nix profile. Walking the
+/// closure needs nix's own database, so this asks `nix-store`.
+fn count_nix_closure(root: &Sysroot, profile: &str, cmd: &dyn CommandRunner) -> Option<usize> {
+    // The profile is a symlink into /nix/store, which may not be captured
+    fs::symlink_metadata(root.path(profile)).ok()?;
+    count_lines(cmd.run("nix-store", &["--query", "--requisites", profile]))
+}
+
+/// Packages installed into the user's nix profile: the elements of a
+/// `nix profile` manifest, or the references of a `nix-env` environment.
+fn count_nix_user(home: Option<&Path>, cmd: &dyn CommandRunner) -> Option<usize> {
+    let home = home?;
+    let profile = [".nix-profile", ".local/state/nix/profiles/profile"]
+        .iter()
+        .map(|path| home.join(path))
+        .find(|path| path.exists())?;
+
+    if let Ok(manifest) = fs::read_to_string(profile.join("manifest.json")) {
+        return count_nix_manifest(&manifest);
+    }
+    let references = cmd.run("nix-store", &["--query", "--references", profile.to_str()?])?;
+    // The environment also references its own manifest
+    Some(
+        references
+            .lines()
+            .filter(|path| !path.ends_with("-env-manifest.nix"))
+            .count(),
+    )
+}
+
+/// `elements` is an array up to manifest version 2 and an object keyed by
+/// name from version 3.
+fn count_nix_manifest(content: &str) -> Option<usize> {
+    let manifest: serde_json::Value = serde_json::from_str(content).ok()?;
+    match manifest.get("elements")? {
+        serde_json::Value::Array(elements) => Some(elements.len()),
+        serde_json::Value::Object(elements) => Some(elements.len()),
+        _ => None,
+    }
+}
+
+/// One directory per formula in a Homebrew on Linux Cellar.
+fn count_brew(root: &Sysroot, home: Option<&Path>) -> Option<usize> {
+    let mut cellars = vec![root.path("/home/linuxbrew/.linuxbrew/Cellar")];
+    if let Some(home) = home {
+        cellars.push(home.join(".linuxbrew/Cellar"));
+    }
+    cellars
+        .iter()
+        .filter(|cellar| cellar.exists())
+        .map(|cellar| count_dirs(cellar))
+        .sum()
+}
+
+/// `*.AppImage` files in `~/Applications`, where AppImageLauncher and
+/// Gear Lever keep them.
+fn count_appimages(home: Option<&Path>) -> Option<usize> {
+    let entries = fs::read_dir(home?.join("Applications")).ok()?;
+    Some(
+        entries
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("appimage"))
+            })
+            .count(),
+    )
+}
+
+/// Crates installed with `cargo install`, from `~/.cargo/.crates2.json`.
+fn count_cargo(home: Option<&Path>) -> Option<usize> {
+    let content = fs::read_to_string(home?.join(".cargo/.crates2.json")).ok()?;
+    let crates: serde_json::Value = serde_json::from_str(&content).ok()?;
+    Some(crates.get("installs")?.as_object()?.len())
+}
+
+/// One virtualenv per app installed with pipx (`~/.local/pipx` before 1.3).
+fn count_pipx(home: Option<&Path>) -> Option<usize> {
+    let home = home?;
+    [".local/share/pipx/venvs", ".local/pipx/venvs"]
+        .iter()
+        .map(|path| home.join(path))
+        .find(|path| path.exists())
+        .and_then(|venvs| count_dirs(&venvs))
+}
+
+fn count_dirs(dir: &Path) -> Option<usize> {
+    let entries = fs::read_dir(dir).ok()?;
+    Some(
+        entries
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .count(),
+    )
+}
+
+fn count_lines(output: Option<String>) -> Option<usize> {
+    output.map(|output| output.lines().count())
+}
+
+/// Count the rows of `table` in an SQLite database file without linking
+/// SQLite: find the table's root page in `sqlite_master`, then add up the
+/// cells on every leaf page of its b-tree. Returns `None` for anything that
+/// doesn't look like a well-formed database.
+fn count_sqlite_rows(db: &[u8], table: &str) -> Option<usize> {
+    if !db.starts_with(b"SQLite format 3\0") || db.len() < 100 {
+        return None;
+    }
+    let page_size = match u16::from_be_bytes([db[16], db[17]]) {
+        1 => 65536,
+        n if n >= 512 => n as usize,
+        _ => return None,
+    };
+
+    let mut root_page = None;
+    walk_table_leaves(db, page_size, 1, &mut |page, cell| {
+        if root_page.is_none() {
+            root_page = sqlite_master_root(page, cell, table);
+        }
+    })?;
+
+    let mut rows = 0;
+    walk_table_leaves(db, page_size, root_page?, &mut |_, _| rows += 1)?;
+    Some(rows)
+}
+
+/// Read a SQLite database along with the transactions still sitting in its
+/// `-wal` file. rpm leaves recent installs and removals there until the next
+/// checkpoint, so the main file alone can be behind. `None` if the log can't
+/// be applied.
+fn read_sqlite_with_wal(path: &Path) -> Option<Vec<u8>> {
+    let mut db = fs::read(path).ok()?;
+    let mut wal_path = path.as_os_str().to_owned();
+    wal_path.push("-wal");
+    match fs::read(wal_path) {
+        Ok(wal) if !wal.is_empty() => apply_wal(&mut db, &wal).map(|()| db),
+        _ => Some(db),
+    }
+}
+
+/// Overlay the committed frames of a write-ahead log onto `db`.
+///
+/// Frames are only valid while their salts match the log header and the
+/// running checksum holds; anything after the last valid commit frame is an
+/// unfinished transaction and is ignored, as SQLite itself does.
+fn apply_wal(db: &mut Vec<u8>, wal: &[u8]) -> Option<()> {
+    const HEADER: usize = 32;
+    const FRAME_HEADER: usize = 24;
+    let word = |at: usize| {
+        wal.get(at..at + 4)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+    };
+
+    let big_endian = match word(0)? {
+        0x377f0682 => false,
+        0x377f0683 => true,
+        _ => return None,
+    };
+    let page_size = word(8)? as usize;
+    let db_page_size = match u16::from_be_bytes([*db.get(16)?, *db.get(17)?]) {
+        1 => 65536,
+        n => n as usize,
+    };
+    if page_size != db_page_size {
+        return None;
+    }
+    let salts = wal.get(16..24)?;
+    let mut sum = wal_checksum(&wal[..24], (0, 0), big_endian);
+    if sum != (word(24)?, word(28)?) {
+        return None;
+    }
+
+    let mut pending: Vec<(usize, &[u8])> = Vec::new();
+    let mut at = HEADER;
+    while let Some(frame) = wal.get(at..at + FRAME_HEADER + page_size) {
+        let (header, page) = frame.split_at(FRAME_HEADER);
+        if &header[8..16] != salts {
+            break;
+        }
+        sum = wal_checksum(&header[..8], sum, big_endian);
+        sum = wal_checksum(page, sum, big_endian);
+        if sum != (word(at + 16)?, word(at + 20)?) {
+            break;
+        }
+
+        let page_no = u32::from_be_bytes(header[0..4].try_into().ok()?) as usize;
+        pending.push((page_no, page));
+        let commit_pages = u32::from_be_bytes(header[4..8].try_into().ok()?) as usize;
+        if commit_pages != 0 {
+            db.resize(commit_pages * page_size, 0);
+            for (page_no, page) in pending.drain(..) {
+                let start = page_no.checked_sub(1)? * page_size;
+                db.get_mut(start..start + page_size)?.copy_from_slice(page);
+            }
+        }
+        at += FRAME_HEADER + page_size;
+    }
+    Some(())
+}
+
+/// SQLite's WAL checksum over `data`, continuing from `(s1, s2)`.
+fn wal_checksum(data: &[u8], (mut s1, mut s2): (u32, u32), big_endian: bool) -> (u32, u32) {
+    let read = |chunk: &[u8]| {
+        let bytes: [u8; 4] = chunk.try_into().unwrap();
+        if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    };
+    for pair in data.chunks_exact(8) {
+        s1 = s1.wrapping_add(read(&pair[..4])).wrapping_add(s2);
+        s2 = s2.wrapping_add(read(&pair[4..])).wrapping_add(s1);
+    }
+    (s1, s2)
+}
+
+/// Call `visit(page, cell_offset)` for every cell on the leaf pages of the
+/// table b-tree rooted at `page_no`.
+fn walk_table_leaves(
+    db: &[u8],
+    page_size: usize,
+    page_no: u32,
+    visit: &mut dyn FnMut(&[u8], usize),
+) -> Option<()> {
+    let page_count = db.len() / page_size;
+    let mut stack = vec![page_no];
+    let mut visited = 0;
+
+    while let Some(page_no) = stack.pop() {
+        // A corrupt file could loop forever; no tree has more pages than the file
+        visited += 1;
+        if page_no == 0 || page_no as usize > page_count || visited > page_count {
+            return None;
+        }
+        let start = (page_no as usize - 1) * page_size;
+        let page = &db[start..start + page_size];
+        // Page 1 starts with the 100-byte database header
+        let header = if page_no == 1 { 100 } else { 0 };
+
+        let cells = u16::from_be_bytes([page[header + 3], page[header + 4]]) as usize;
+        let (pointers, leaf) = match page[header] {
+            0x0d => (header + 8, true),
+            0x05 => (header + 12, false),
+            _ => return None,
+        };
+
+        for i in 0..cells {
+            let at = pointers + i * 2;
+            let cell = u16::from_be_bytes([*page.get(at)?, *page.get(at + 1)?]) as usize;
+            if cell >= page_size {
+                return None;
+            }
+            if leaf {
+                visit(page, cell);
+            } else {
+                let child = page.get(cell..cell + 4)?;
+                stack.push(u32::from_be_bytes(child.try_into().ok()?));
+            }
+        }
+        if !leaf {
+            let right = page.get(header + 8..header + 12)?;
+            stack.push(u32::from_be_bytes(right.try_into().ok()?));
+        }
+    }
+    Some(())
+}
+
+/// If the `sqlite_master` row at `cell` describes the table `name`, return
+/// its root page.
+///
+/// Rows are `(type, name, tbl_name, rootpage, sql)`. Only the first four
+/// columns are read, and those always sit in the cell's local payload.
+fn sqlite_master_root(page: &[u8], cell: usize, name: &str) -> Option<u32> {
+    let (_, n) = read_varint(page.get(cell..)?)?; // payload size
+    let mut at = cell + n;
+    let (_, n) = read_varint(page.get(at..)?)?; // rowid
+    at += n;
+
+    let record = at;
+    let (header_len, n) = read_varint(page.get(at..)?)?;
+    at += n;
+    let mut types = Vec::new();
+    while at < record + header_len as usize && types.len() < 4 {
+        let (serial, n) = read_varint(page.get(at..)?)?;
+        types.push(serial);
+        at += n;
+    }
+    if types.len() < 4 {
+        return None;
+    }
+
+    let mut body = record + header_len as usize;
+    let mut columns = Vec::new();
+    for serial in types {
+        let len = serial_len(serial);
+        columns.push((serial, page.get(body..body + len)?));
+        body += len;
+    }
+
+    let is_table = columns[0].1 == b"table";
+    let matches = columns[1].1 == name.as_bytes();
+    let (root_serial, root) = columns[3];
+    if !is_table || !matches || !(1..=6).contains(&root_serial) {
+        return None;
+    }
+    Some(root.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64) as u32)
+}
+
+/// Byte length of a record value with the given serial type.
+fn serial_len(serial: u64) -> usize {
+    match serial {
+        0 | 8 | 9 => 0,
+        1..=4 => serial as usize,
+        5 => 6,
+        6 | 7 => 8,
+        n if n >= 12 => ((n - 12) / 2) as usize,
+        _ => 0,
+    }
+}
+
+/// SQLite's big-endian varint: up to 9 bytes, 7 bits each except the last.
+/// Returns the value and the number of bytes read.
+fn read_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(9) {
+        if i == 8 {
+            return Some(((value << 8) | byte as u64, 9));
+        }
+        value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Count the used package slots in an rpm ndb `Packages.db`.
+///
+/// The file starts with slot pages: a 32-byte header (`RpmP` magic, version,
+/// generation, number of slot pages, ...) followed by 16-byte slots of
+/// `Slot` magic, package index, block offset and block count. A slot with a
+/// non-zero package index holds an installed package.
+fn count_ndb_packages(db: &[u8]) -> Option<usize> {
+    const HEADER_SIZE: usize = 32;
+    const SLOT_SIZE: usize = 16;
+    const PAGE_SIZE: usize = 4096;
+
+    let u32_at = |at: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(db.get(at..at + 4)?.try_into().ok()?))
+    };
+    if db.get(0..4)? != b"RpmP" {
+        return None;
+    }
+    let slot_pages = u32_at(12)? as usize;
+    let end = (slot_pages * PAGE_SIZE).min(db.len());
+
+    let mut count = 0;
+    for at in (HEADER_SIZE..end).step_by(SLOT_SIZE) {
+        if db.get(at..at + 4)? != b"Slot" {
+            continue;
+        }
+        if u32_at(at + 4)? != 0 {
+            count += 1;
+        }
+    }
+    Some(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+
+    #[test]
+    fn test_get_packages_reads_databases() {
+        let packages =
+            |machine| get_packages(&fixtures::root(machine), None, &fixtures::commands(machine));
+        assert_eq!(packages("arch-desktop"), "64 (pacman), 3 (flatpak)");
+        assert_eq!(
+            packages("ubuntu-thinkpad"),
+            "41 (dpkg), 3 (brew), 10 (snap)"
+        );
+        assert_eq!(packages("fedora-vm"), "108 (rpm)");
+        assert_eq!(packages("alpine-docker"), "21 (apk)");
+        assert_eq!(packages("void-laptop"), "25 (xbps)");
+        assert_eq!(packages("gentoo-workstation"), "19 (portage)");
+    }
+
+    #[test]
+    fn test_get_packages_counts_nix_and_user_installs() {
+        let machine = "nixos-framework";
+        let home = fixtures::home(machine);
+        assert_eq!(
+            get_packages(
+                &fixtures::root(machine),
+                Some(&home),
+                &fixtures::commands(machine)
+            ),
+            "158 (nix-system), 10 (nix-default), 5 (nix-user), 2 (appimage), 3 (cargo), 2 (pipx)"
+        );
+    }
+
+    #[test]
+    fn test_count_nix_manifest_versions() {
+        assert_eq!(
+            count_nix_manifest(r#"{"version":2,"elements":[{},{}]}"#),
+            Some(2)
+        );
+        assert_eq!(
+            count_nix_manifest(r#"{"version":3,"elements":{"jq":{},"fd":{},"bat":{}}}"#),
+            Some(3)
+        );
+        assert_eq!(count_nix_manifest("{}"), None);
+    }
+
+    #[test]
+    fn test_count_dpkg_status_skips_removed_packages() {
+        let status = "\
+Package: adduser
+Status: install ok installed
+Version: 3.137ubuntu1
+
+Package: old-kernel
+Status: deinstall ok config-files
+Version: 6.8.0-31.31
+
+Package: zstd
+Status: install ok installed
+Version: 1.5.5+dfsg2-2build1.1
+";
+        assert_eq!(count_dpkg_status(status), 2);
+    }
+
+    #[test]
+    fn test_read_varint() {
+        assert_eq!(read_varint(&[0x05]), Some((5, 1)));
+        assert_eq!(read_varint(&[0x81, 0x00]), Some((128, 2)));
+        assert_eq!(read_varint(&[0x81]), None);
+    }
+
+    #[test]
+    fn test_count_sqlite_rows() {
+        let db = fixtures::root("fedora-vm").path("/var/lib/rpm/rpmdb.sqlite");
+        let db = fs::read(db).unwrap();
+        assert_eq!(count_sqlite_rows(&db, "Packages"), Some(107));
+        assert_eq!(count_sqlite_rows(&db, "Name"), Some(107));
+        assert_eq!(count_sqlite_rows(&db, "Missing"), None);
+        assert_eq!(count_sqlite_rows(b"not a database", "Packages"), None);
+    }
+
+    #[test]
+    fn test_sqlite_wal_is_applied() {
+        // Two installs and a removal not yet checkpointed into the main file
+        let path = fixtures::root("fedora-vm").path("/var/lib/rpm/rpmdb.sqlite");
+        let main = fs::read(&path).unwrap();
+        assert_eq!(count_sqlite_rows(&main, "Packages"), Some(107));
+        let db = read_sqlite_with_wal(&path).unwrap();
+        assert_eq!(count_sqlite_rows(&db, "Packages"), Some(108));
+
+        let mut wal_path = path.as_os_str().to_owned();
+        wal_path.push("-wal");
+        let wal = fs::read(wal_path).unwrap();
+
+        // A torn final frame drops that transaction, not the database
+        let mut db = main.clone();
+        apply_wal(&mut db, &wal[..wal.len() - 100]).unwrap();
+        assert_eq!(count_sqlite_rows(&db, "Packages"), Some(107));
+
+        // Frames from an older log generation carry stale salts
+        let mut stale = wal.clone();
+        stale[32 + 8] ^= 0xff;
+        let mut db = main.clone();
+        apply_wal(&mut db, &stale).unwrap();
+        assert_eq!(db, main);
+
+        assert!(apply_wal(&mut main.clone(), b"not a wal").is_none());
+    }
+
+    #[test]
+    fn test_count_ndb_packages() {
+        let mut db = vec![0u8; 4096];
+        db[0..4].copy_from_slice(b"RpmP");
+        db[12..16].copy_from_slice(&1u32.to_le_bytes());
+        for (slot, pkgidx) in [(0usize, 1u32), (1, 0), (2, 7)] {
+            let at = 32 + slot * 16;
+            db[at..at + 4].copy_from_slice(b"Slot");
+            db[at + 4..at + 8].copy_from_slice(&pkgidx.to_le_bytes());
+        }
+        assert_eq!(count_ndb_packages(&db), Some(2));
+        assert_eq!(count_ndb_packages(b"Slot"), None);
+    }
+}
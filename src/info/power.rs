@@ -0,0 +1,225 @@
+//! Batteries, AC adapters and the ACPI platform profile.
+
+use super::sysroot::Sysroot;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// One battery from `/sys/class/power_supply`.
+#[derive(Clone, Debug, Serialize)]
+pub struct BatteryInfo {
+    /// Kernel name, e.g. `BAT0`.
+    pub name: String,
+    pub model: Option<String>,
+    pub capacity_percent: Option<u8>,
+    /// `charging`, `discharging`, `full`, `not charging` or `unknown`.
+    pub status: String,
+    /// Full charge capacity as a share of the design capacity.
+    pub health_percent: Option<u8>,
+    pub cycle_count: Option<u32>,
+    /// Until empty when discharging, until full when charging.
+    pub time_remaining_secs: Option<u64>,
+}
+
+impl fmt::Display for BatteryInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.capacity_percent {
+            Some(capacity) => write!(f, "{}% ({}", capacity, self.status)?,
+            None => write!(f, "({}", self.status)?,
+        }
+        if let Some(secs) = self.time_remaining_secs {
+            let until = if self.status == "charging" {
+                "until full"
+            } else {
+                "left"
+            };
+            write!(f, ", {}h {:02}m {}", secs / 3600, secs % 3600 / 60, until)?;
+        }
+        write!(f, ")")?;
+        if let Some(health) = self.health_percent {
+            write!(f, ", health {}%", health)?;
+        }
+        if let Some(cycles) = self.cycle_count {
+            write!(f, ", {} cycles", cycles)?;
+        }
+        Ok(())
+    }
+}
+
+/// Batteries plus the machine-wide power state.
+#[derive(Clone, Debug, Serialize)]
+pub struct PowerInfo {
+    pub batteries: Vec<BatteryInfo>,
+    /// Whether a mains or USB-C adapter is supplying power, when the machine
+    /// reports adapters at all.
+    pub ac_online: Option<bool>,
+    /// Active `/sys/firmware/acpi/platform_profile`, e.g. `balanced`.
+    pub platform_profile: Option<String>,
+}
+
+impl PowerInfo {
+    /// The adapter and profile, as shown after the battery lines.
+    pub fn summary(&self) -> Option<String> {
+        let ac = self.ac_online.map(|online| {
+            if online {
+                "AC connected".to_string()
+            } else {
+                "On battery".to_string()
+            }
+        });
+        let profile = self
+            .platform_profile
+            .as_ref()
+            .map(|profile| format!("{} profile", profile));
+        let parts: Vec<String> = ac.into_iter().chain(profile).collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+/// System batteries, adapters and the platform profile. `None` on machines
+/// without a battery.
+///
+/// Batteries in peripherals (mice, headsets) report `scope=Device` and are
+/// left out.
+pub fn get_power(root: &Sysroot) -> Option<PowerInfo> {
+    let entries = root.read_dir("/sys/class/power_supply").ok()?;
+    let mut supplies: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    supplies.sort();
+
+    let mut batteries = Vec::new();
+    let mut ac_online = None;
+    for supply in &supplies {
+        let read = |file: &str| read_value(supply, file);
+        if read("scope").as_deref() == Some("Device") {
+            continue;
+        }
+        match read("type").as_deref() {
+            Some("Battery") if read("present").as_deref() != Some("0") => {
+                batteries.push(read_battery(supply));
+            }
+            Some("Mains" | "USB") if let Some(online) = read("online") => {
+                ac_online = Some(ac_online.unwrap_or(false) || online == "1");
+            }
+            _ => {}
+        }
+    }
+
+    if batteries.is_empty() {
+        return None;
+    }
+    Some(PowerInfo {
+        batteries,
+        ac_online,
+        platform_profile: root
+            .read_trimmed("/sys/firmware/acpi/platform_profile")
+            .filter(|profile| !profile.is_empty()),
+    })
+}
+
+fn read_value(dir: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(dir.join(file))
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn read_battery(dir: &Path) -> BatteryInfo {
+    let read = |file: &str| read_value(dir, file);
+    let number = |file: &str| read(file).and_then(|v| v.parse::<u64>().ok());
+
+    // Batteries report either energy (µWh, µW) or charge (µAh, µA)
+    let (now, full, design, rate) = if number("energy_full").is_some() {
+        (
+            number("energy_now"),
+            number("energy_full"),
+            number("energy_full_design"),
+            number("power_now"),
+        )
+    } else {
+        (
+            number("charge_now"),
+            number("charge_full"),
+            number("charge_full_design"),
+            number("current_now"),
+        )
+    };
+
+    let status = read("status")
+        .unwrap_or_else(|| "Unknown".to_string())
+        .to_lowercase();
+    let capacity_percent = number("capacity")
+        .or_else(|| Some(now? * 100 / full.filter(|&f| f > 0)?))
+        .map(|percent| percent.min(100) as u8);
+    let health_percent = match (full, design) {
+        (Some(full), Some(design)) if design > 0 => Some((full * 100 / design).min(100) as u8),
+        _ => None,
+    };
+
+    let rate = rate.filter(|&r| r > 0);
+    let time_remaining_secs = match status.as_str() {
+        "discharging" => number("time_to_empty_now").or_else(|| Some(now? * 3600 / rate?)),
+        "charging" => {
+            number("time_to_full_now").or_else(|| Some(full?.saturating_sub(now?) * 3600 / rate?))
+        }
+        _ => None,
+    };
+
+    BatteryInfo {
+        name: dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        model: read("model_name"),
+        capacity_percent,
+        status,
+        health_percent,
+        // Some firmware reports 0 when it doesn't count cycles
+        cycle_count: number("cycle_count").filter(|&c| c > 0).map(|c| c as u32),
+        time_remaining_secs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+
+    #[test]
+    fn test_discharging_energy_battery() {
+        let power = get_power(&fixtures::root("ubuntu-thinkpad")).unwrap();
+        assert_eq!(power.batteries.len(), 1);
+        let battery = &power.batteries[0];
+        assert_eq!(battery.capacity_percent, Some(81));
+        assert_eq!(battery.status, "discharging");
+        assert_eq!(battery.health_percent, Some(91));
+        assert_eq!(battery.cycle_count, Some(214));
+        assert_eq!(
+            battery.to_string(),
+            "81% (discharging, 3h 42m left), health 91%, 214 cycles"
+        );
+        assert_eq!(power.ac_online, Some(false));
+        assert_eq!(
+            power.summary().as_deref(),
+            Some("On battery, balanced profile")
+        );
+    }
+
+    #[test]
+    fn test_charging_charge_battery_skips_peripherals() {
+        let power = get_power(&fixtures::root("void-laptop")).unwrap();
+        // The mouse's battery (scope=Device) is not listed
+        assert_eq!(power.batteries.len(), 1);
+        assert_eq!(
+            power.batteries[0].to_string(),
+            "45% (charging, 1h 12m until full), health 87%"
+        );
+        assert_eq!(power.ac_online, Some(true));
+        assert_eq!(power.platform_profile, None);
+    }
+
+    #[test]
+    fn test_no_battery_on_desktop() {
+        assert!(get_power(&fixtures::root("arch-desktop")).is_none());
+    }
+}
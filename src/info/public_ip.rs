@@ -0,0 +1,247 @@
+//! The public address as seen by an external "what is my IP" endpoint.
+
+use super::runner::CommandRunner;
+use crate::config::PublicIpConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Responses are a single address; anything longer is not one.
+const MAX_RESPONSE_BYTES: u64 = 4096;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PublicIpInfo {
+    pub ipv4: Option<String>,
+    pub ipv6: Option<String>,
+}
+
+impl fmt::Display for PublicIpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let addresses: Vec<&str> = self
+            .ipv4
+            .iter()
+            .chain(&self.ipv6)
+            .map(String::as_str)
+            .collect();
+        write!(f, "{}", addresses.join(", "))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Family {
+    V4,
+    V6,
+}
+
+impl Family {
+    fn matches(self, addr: &IpAddr) -> bool {
+        match self {
+            Family::V4 => addr.is_ipv4(),
+            Family::V6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// Ask `config.url` for our address over IPv4 and over IPv6, in parallel.
+/// `None` when neither family got an answer, e.g. offline.
+pub fn get_public_ip(cmd: &dyn CommandRunner, config: &PublicIpConfig) -> Option<PublicIpInfo> {
+    let timeout = Duration::from_millis(config.timeout_ms);
+    let (ipv4, ipv6) = thread::scope(|scope| {
+        let v6 = scope.spawn(|| fetch(cmd, &config.url, Family::V6, timeout));
+        let v4 = fetch(cmd, &config.url, Family::V4, timeout);
+        (v4, v6.join().ok().flatten())
+    });
+    if ipv4.is_none() && ipv6.is_none() {
+        return None;
+    }
+    Some(PublicIpInfo {
+        ipv4: ipv4.map(|ip| ip.to_string()),
+        ipv6: ipv6.map(|ip| ip.to_string()),
+    })
+}
+
+fn fetch(cmd: &dyn CommandRunner, url: &str, family: Family, timeout: Duration) -> Option<IpAddr> {
+    let body = if url.starts_with("http://") {
+        http_get(url, family, timeout)?
+    } else {
+        // No TLS stack of our own; curl enforces the same limit
+        let flag = match family {
+            Family::V4 => "-4",
+            Family::V6 => "-6",
+        };
+        cmd.run(
+            "curl",
+            &["-fsS", flag, "--max-time", &max_time(timeout), url],
+        )?
+    };
+    let addr: IpAddr = body.trim().parse().ok()?;
+    family.matches(&addr).then_some(addr)
+}
+
+/// `timeout` as curl's `--max-time` seconds. curl reads 0 as no limit at
+/// all, so anything shorter than a millisecond rounds up to one.
+fn max_time(timeout: Duration) -> String {
+    format!("{:.3}", timeout.as_secs_f64().max(0.001))
+}
+
+/// A plain HTTP/1.0 GET over `family` only. `timeout` bounds the whole
+/// exchange, name resolution included, not each step.
+fn http_get(url: &str, family: Family, timeout: Duration) -> Option<String> {
+    let deadline = Instant::now() + timeout;
+    let rest = url.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    };
+    let host = authority
+        .rsplit_once(':')
+        .filter(|(_, port)| port.chars().all(|c| c.is_ascii_digit()))
+        .map_or(authority, |(host, _)| host);
+    let with_port = if host == authority {
+        format!("{}:80", authority)
+    } else {
+        authority.to_string()
+    };
+
+    let addrs: Vec<SocketAddr> = resolve(with_port, timeout)?
+        .into_iter()
+        .filter(|addr| family.matches(&addr.ip()))
+        .collect();
+    let mut stream = addrs.iter().find_map(|addr| {
+        TcpStream::connect_timeout(addr, deadline.checked_duration_since(Instant::now())?).ok()
+    })?;
+
+    let remaining = deadline.checked_duration_since(Instant::now())?;
+    stream.set_write_timeout(Some(remaining)).ok()?;
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: ghostfetch/{}\r\nAccept: text/plain\r\n\r\n",
+        path,
+        authority,
+        env!("CARGO_PKG_VERSION")
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+
+    let mut response = Vec::new();
+    let mut limited = (&stream).take(MAX_RESPONSE_BYTES);
+    let mut buf = [0u8; 512];
+    loop {
+        let remaining = deadline.checked_duration_since(Instant::now())?;
+        stream.set_read_timeout(Some(remaining)).ok()?;
+        match limited.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+            Err(_) => return None,
+        }
+    }
+
+    let response = String::from_utf8(response).ok()?;
+    let (head, body) = response.split_once("\r\n\r\n")?;
+    let status = head.lines().next()?.split_whitespace().nth(1)?;
+    (status == "200").then(|| body.to_string())
+}
+
+/// Look up `host_port` but give up after `timeout`. getaddrinfo can't be
+/// cancelled, so a lookup that outlives the deadline finishes on its own
+/// thread and its answer is dropped.
+fn resolve(host_port: String, timeout: Duration) -> Option<Vec<SocketAddr>> {
+    let (tx, rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("public-ip-dns".to_string())
+        .spawn(move || {
+            let addrs = host_port.to_socket_addrs().map(Iterator::collect);
+            let _ = tx.send(addrs);
+        })
+        .ok()?;
+    rx.recv_timeout(timeout).ok()?.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+    use std::net::TcpListener;
+
+    /// Serve one canned response on `listener` and return the request.
+    fn serve_once(listener: TcpListener, response: &'static str) -> thread::JoinHandle<String> {
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let n = stream.read(&mut request).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&request[..n]).into_owned()
+        })
+    }
+
+    fn config(url: String) -> PublicIpConfig {
+        PublicIpConfig {
+            enabled: true,
+            url,
+            timeout_ms: 1000,
+        }
+    }
+
+    #[test]
+    fn test_http_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ip", listener.local_addr().unwrap());
+        let server = serve_once(listener, "HTTP/1.0 200 OK\r\n\r\n203.0.113.7\n");
+
+        // A literal IPv4 endpoint can't be reached over IPv6
+        let info = get_public_ip(&fixtures::commands("fedora-vm"), &config(url)).unwrap();
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /ip HTTP/1.0\r\n"));
+        assert_eq!(info.ipv4.as_deref(), Some("203.0.113.7"));
+        assert_eq!(info.ipv6, None);
+        assert_eq!(info.to_string(), "203.0.113.7");
+    }
+
+    #[test]
+    fn test_http_errors_and_garbage() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = serve_once(listener, "HTTP/1.0 503 Unavailable\r\n\r\n203.0.113.7\n");
+        assert!(http_get(&url, Family::V4, Duration::from_secs(1)).is_none());
+        server.join().unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = serve_once(
+            listener,
+            "HTTP/1.0 200 OK\r\n\r\n<html>captive portal</html>",
+        );
+        assert!(get_public_ip(&fixtures::commands("fedora-vm"), &config(url)).is_none());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_timeout_is_enforced() {
+        // Accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let started = Instant::now();
+        assert!(http_get(&url, Family::V4, Duration::from_millis(200)).is_none());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_curl_max_time_is_never_zero() {
+        assert_eq!(max_time(Duration::from_millis(1000)), "1.000");
+        assert_eq!(max_time(Duration::from_millis(40)), "0.040");
+        assert_eq!(max_time(Duration::ZERO), "0.001");
+    }
+
+    #[test]
+    fn test_https_through_curl() {
+        let info = get_public_ip(
+            &fixtures::commands("arch-desktop"),
+            &PublicIpConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(info.to_string(), "198.51.100.21, 2001:db8:21::21");
+    }
+}
@@ -0,0 +1,154 @@
+use super::helpers::run_cmd;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Runs external helper commands on behalf of the collectors.
+///
+/// Collectors take a `&dyn CommandRunner` instead of spawning processes
+/// themselves, so their parsers can be fed recorded output in tests and on
+/// machines that don't have the tools installed.
+pub trait CommandRunner: Send + Sync {
+    /// Run `program` with `args` and return its stdout, or `None` if it cannot
+    /// be run or does not finish in time.
+    fn run(&self, program: &str, args: &[&str]) -> Option<String>;
+}
+
+/// Spawns real processes through [`run_cmd`], with its timeout.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Option<String> {
+        run_cmd(program, args).map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Runs real processes and saves each output into a fixture directory, in
+/// the layout [`ReplayRunner`] reads.
+pub struct RecordingRunner {
+    dir: PathBuf,
+}
+
+impl RecordingRunner {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        RecordingRunner { dir: dir.into() }
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Option<String> {
+        let stdout = SystemRunner.run(program, args)?;
+        let _ = fs::create_dir_all(&self.dir);
+        let _ = fs::write(self.dir.join(fixture_name(program, args)), &stdout);
+        Some(stdout)
+    }
+}
+
+/// Serves command output from fixture files instead of running anything.
+///
+/// A command without a fixture file behaves like a missing binary.
+pub struct ReplayRunner {
+    dir: PathBuf,
+}
+
+impl ReplayRunner {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ReplayRunner { dir: dir.into() }
+    }
+}
+
+impl CommandRunner for ReplayRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Option<String> {
+        fs::read_to_string(self.dir.join(fixture_name(program, args))).ok()
+    }
+}
+
+/// File name for a command's recorded output: the program name (without any
+/// directory) and its arguments joined by `_`, with anything outside
+/// `[A-Za-z0-9._-]` replaced, e.g. `lspci_-mm` or `ip_-4_addr_show_scope_global`.
+pub fn fixture_name(program: &str, args: &[&str]) -> String {
+    let program = Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program);
+
+    std::iter::once(program)
+        .chain(args.iter().copied())
+        .collect::<Vec<_>>()
+        .join("_")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Fixture helpers shared by the collector tests.
+#[cfg(test)]
+pub mod fixtures {
+    use super::ReplayRunner;
+    use crate::info::Sysroot;
+    use std::path::PathBuf;
+
+    pub fn machine_dir(machine: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(machine)
+    }
+
+    /// Replay the commands recorded for a fixture machine.
+    pub fn commands(machine: &str) -> ReplayRunner {
+        ReplayRunner::new(machine_dir(machine).join("commands"))
+    }
+
+    /// The captured filesystem of a fixture machine.
+    pub fn root(machine: &str) -> Sysroot {
+        Sysroot::new(machine_dir(machine).join("root"))
+    }
+
+    /// The captured home directory of a fixture machine's user.
+    pub fn home(machine: &str) -> PathBuf {
+        machine_dir(machine).join("home")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_name() {
+        assert_eq!(fixture_name("lspci", &["-mm"]), "lspci_-mm");
+        assert_eq!(
+            fixture_name("ip", &["-4", "addr", "show", "scope", "global"]),
+            "ip_-4_addr_show_scope_global"
+        );
+        assert_eq!(
+            fixture_name("/usr/bin/zsh", &["--version"]),
+            "zsh_--version"
+        );
+        assert_eq!(
+            fixture_name("dpkg-query", &["-f", ".\n", "-W"]),
+            "dpkg-query_-f_.__-W"
+        );
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let dir =
+            std::env::temp_dir().join(format!("ghostfetch-runner-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let recorded = RecordingRunner::new(&dir).run("echo", &["hello"]);
+        assert_eq!(recorded.as_deref(), Some("hello\n"));
+
+        let replay = ReplayRunner::new(&dir);
+        assert_eq!(replay.run("echo", &["hello"]).as_deref(), Some("hello\n"));
+        assert_eq!(replay.run("echo", &["other"]), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
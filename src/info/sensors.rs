@@ -0,0 +1,296 @@
+//! Temperatures and fan speeds from `/sys/class/hwmon`.
+
+use super::sysroot::Sysroot;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What a temperature sensor measures, for the one-line summary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorCategory {
+    Cpu,
+    Gpu,
+    Nvme,
+    Chipset,
+}
+
+impl SensorCategory {
+    const SUMMARY_ORDER: [SensorCategory; 4] = [
+        SensorCategory::Cpu,
+        SensorCategory::Gpu,
+        SensorCategory::Nvme,
+        SensorCategory::Chipset,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SensorCategory::Cpu => "CPU",
+            SensorCategory::Gpu => "GPU",
+            SensorCategory::Nvme => "NVMe",
+            SensorCategory::Chipset => "Chipset",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Temperature {
+    /// hwmon driver name, e.g. `k10temp`.
+    pub chip: String,
+    /// `temp*_label`, or `temp<n>` when the driver has none.
+    pub label: String,
+    pub celsius: f64,
+    pub category: Option<SensorCategory>,
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {:.1}°C", self.chip, self.label, self.celsius)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Fan {
+    pub chip: String,
+    pub label: String,
+    pub rpm: u32,
+}
+
+impl fmt::Display for Fan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {} RPM", self.chip, self.label, self.rpm)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SensorsInfo {
+    pub temperatures: Vec<Temperature>,
+    pub fans: Vec<Fan>,
+    /// Render every sensor on its own line instead of the summary.
+    #[serde(skip)]
+    pub show_all: bool,
+}
+
+impl SensorsInfo {
+    /// `CPU 52°C, GPU 43°C, NVMe 39°C, Chipset 48°C, Fan 1180 RPM`: the
+    /// headline sensor for each category and the fastest fan.
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = SensorCategory::SUMMARY_ORDER
+            .iter()
+            .filter_map(|&category| {
+                let hottest = self
+                    .temperatures
+                    .iter()
+                    .filter(|t| t.category == Some(category))
+                    .map(|t| t.celsius)
+                    .reduce(f64::max)?;
+                Some(format!("{} {:.0}°C", category.name(), hottest))
+            })
+            .collect();
+        if let Some(rpm) = self.fans.iter().map(|fan| fan.rpm).max().filter(|&r| r > 0) {
+            parts.push(format!("Fan {} RPM", rpm));
+        }
+        if parts.is_empty() {
+            // Only uncategorized sensors, e.g. ACPI thermal zones
+            parts.extend(self.temperatures.first().map(|t| t.to_string()));
+        }
+        parts.join(", ")
+    }
+}
+
+/// hwmon drivers that report the CPU package, with the sensor label to prefer
+/// from each. k10temp's Tctl carries an offset on some older Ryzens, but it's
+/// what the fan curve follows and what other tools show.
+const CPU_HWMON_SENSORS: &[(&str, &str)] = &[
+    ("k10temp", "Tctl"),
+    ("zenpower", "Tdie"),
+    ("coretemp", "Package id 0"),
+];
+
+/// GPU drivers with hwmon temperatures, with the sensor to summarize.
+/// amdgpu's `edge` is the die average; `junction` is the hotspot.
+const GPU_HWMON_SENSORS: &[(&str, &str)] = &[
+    ("amdgpu", "edge"),
+    ("radeon", "temp1"),
+    ("nouveau", "temp1"),
+    ("xe", "pkg"),
+];
+
+/// Every labelled temperature and fan, in hwmon order. `None` when the
+/// machine exposes no hwmon sensors (VMs, containers).
+pub fn get_sensors(root: &Sysroot) -> Option<SensorsInfo> {
+    let mut info = SensorsInfo {
+        temperatures: Vec::new(),
+        fans: Vec::new(),
+        show_all: false,
+    };
+
+    for (chip, dir) in hwmon_chips(root) {
+        for (label, celsius) in read_inputs(&dir, "temp") {
+            let celsius = celsius / 1000.0;
+            let category = categorize(&chip, &label);
+            info.temperatures.push(Temperature {
+                chip: chip.clone(),
+                label,
+                celsius,
+                category,
+            });
+        }
+        for (label, rpm) in read_inputs(&dir, "fan") {
+            info.fans.push(Fan {
+                chip: chip.clone(),
+                label,
+                rpm: rpm as u32,
+            });
+        }
+    }
+
+    (!info.temperatures.is_empty() || !info.fans.is_empty()).then_some(info)
+}
+
+/// The CPU package temperature, from the preferred sensor of the first CPU
+/// hwmon driver.
+pub(super) fn cpu_temperature(root: &Sysroot) -> Option<f64> {
+    hwmon_chips(root).into_iter().find_map(|(chip, dir)| {
+        let (_, preferred) = CPU_HWMON_SENSORS.iter().find(|(n, _)| *n == chip)?;
+        let temps = read_inputs(&dir, "temp");
+        let (_, millis) = temps
+            .iter()
+            .find(|(label, _)| label == preferred)
+            .or_else(|| temps.first())?;
+        Some(millis / 1000.0)
+    })
+}
+
+fn categorize(chip: &str, label: &str) -> Option<SensorCategory> {
+    let preferred = |table: &[(&str, &str)]| {
+        table
+            .iter()
+            .any(|&(name, sensor)| name == chip && sensor == label)
+    };
+    if preferred(CPU_HWMON_SENSORS) {
+        Some(SensorCategory::Cpu)
+    } else if preferred(GPU_HWMON_SENSORS) {
+        Some(SensorCategory::Gpu)
+    } else if chip == "nvme" && label == "Composite" {
+        Some(SensorCategory::Nvme)
+    } else if chip.starts_with("pch_")
+        || label.eq_ignore_ascii_case("chipset")
+        || label.starts_with("PCH_CHIP")
+    {
+        Some(SensorCategory::Chipset)
+    } else {
+        None
+    }
+}
+
+/// `(driver name, directory)` for each `hwmon*` device, in index order.
+fn hwmon_chips(root: &Sysroot) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = root.read_dir("/sys/class/hwmon") else {
+        return Vec::new();
+    };
+    let mut chips: Vec<(u32, String, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let index = entry
+                .file_name()
+                .to_string_lossy()
+                .strip_prefix("hwmon")?
+                .parse()
+                .ok()?;
+            let dir = entry.path();
+            let name = fs::read_to_string(dir.join("name"))
+                .ok()?
+                .trim()
+                .to_string();
+            Some((index, name, dir))
+        })
+        .collect();
+    chips.sort_by_key(|(index, _, _)| *index);
+    chips
+        .into_iter()
+        .map(|(_, name, dir)| (name, dir))
+        .collect()
+}
+
+/// `(label, raw value)` for each `<kind><n>_input` in `dir`, in index order.
+fn read_inputs(dir: &Path, kind: &str) -> Vec<(String, f64)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut indices: Vec<u32> = entries
+        .flatten()
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .strip_prefix(kind)?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    indices.sort_unstable();
+
+    indices
+        .into_iter()
+        .filter_map(|index| {
+            let value: f64 = fs::read_to_string(dir.join(format!("{}{}_input", kind, index)))
+                .ok()?
+                .trim()
+                .parse()
+                .ok()?;
+            let label = fs::read_to_string(dir.join(format!("{}{}_label", kind, index)))
+                .map(|label| label.trim().to_string())
+                .ok()
+                .filter(|label| !label.is_empty())
+                .unwrap_or_else(|| format!("{}{}", kind, index));
+            Some((label, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+
+    #[test]
+    fn test_sensors_summary() {
+        let sensors = get_sensors(&fixtures::root("arch-desktop")).unwrap();
+        assert_eq!(
+            sensors.summary(),
+            "CPU 52°C, GPU 43°C, NVMe 39°C, Chipset 55°C, Fan 1180 RPM"
+        );
+        let tctl = &sensors.temperatures[3];
+        assert_eq!(tctl.to_string(), "k10temp Tctl: 52.4°C");
+        assert_eq!(tctl.category, Some(SensorCategory::Cpu));
+        // Stopped fans are still listed
+        assert_eq!(sensors.fans.len(), 3);
+        assert_eq!(sensors.fans[0].rpm, 0);
+    }
+
+    #[test]
+    fn test_sensors_without_labels() {
+        let sensors = get_sensors(&fixtures::root("ubuntu-thinkpad")).unwrap();
+        assert_eq!(sensors.temperatures[0].label, "temp1");
+        assert_eq!(sensors.temperatures[0].category, None);
+        assert_eq!(sensors.summary(), "CPU 47°C, Fan 2400 RPM");
+
+        assert!(get_sensors(&fixtures::root("fedora-vm")).is_none());
+    }
+
+    #[test]
+    fn test_cpu_temperature_prefers_labelled_sensor() {
+        // zenpower lists Tdie before Tctl; coretemp comes after acpitz
+        assert_eq!(
+            cpu_temperature(&fixtures::root("gentoo-workstation")),
+            Some(45.125)
+        );
+        assert_eq!(
+            cpu_temperature(&fixtures::root("ubuntu-thinkpad")),
+            Some(47.0)
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use super::helpers::run_cmd;
+use super::runner::CommandRunner;
+use super::sysroot::Sysroot;
 use std::fs;
 
-pub fn get_os_info() -> (String, String) {
-    let content = fs::read_to_string("/etc/os-release").unwrap_or_default();
+pub fn get_os_info(root: &Sysroot) -> (String, String) {
+    let content = root
+        .read_to_string("/etc/os-release")
+        .or_else(|_| root.read_to_string("/usr/lib/os-release"))
+        .unwrap_or_default();
     let mut pretty_name = String::from("Linux");
     let mut id = String::from("linux");
 
@@ -19,94 +23,36 @@ pub fn get_os_info() -> (String, String) {
     (pretty_name, id)
 }
 
-pub fn get_kernel() -> String {
-    fs::read_to_string("/proc/sys/kernel/osrelease")
+pub fn get_kernel(root: &Sysroot) -> String {
+    root.read_to_string("/proc/sys/kernel/osrelease")
         .map(|s| s.trim().to_string())
         .unwrap_or_else(|_| "Unknown".to_string())
 }
 
-pub fn get_shell() -> String {
+pub fn get_shell(cmd: &dyn CommandRunner) -> String {
     let shell = std::env::var("SHELL")
         .map(|s| s.rsplit('/').next().unwrap_or("Unknown").to_string())
         .unwrap_or_else(|_| "Unknown".to_string());
 
     // Try to get version
-    if let Some(output) = run_cmd(&shell, &["--version"]) {
-        let version_str = String::from_utf8_lossy(&output.stdout);
-        if let Some(first_line) = version_str.lines().next() {
-            // Extract version number
-            for part in first_line.split_whitespace() {
-                if part
-                    .chars()
-                    .next()
-                    .map(|c| c.is_ascii_digit())
-                    .unwrap_or(false)
-                {
-                    return format!("{} {}", shell, part);
-                }
+    if let Some(version_str) = cmd.run(&shell, &["--version"])
+        && let Some(first_line) = version_str.lines().next()
+    {
+        // Extract version number
+        for part in first_line.split_whitespace() {
+            if part
+                .chars()
+                .next()
+                .map(|c| c.is_ascii_digit())
+                .unwrap_or(false)
+            {
+                return format!("{} {}", shell, part);
             }
         }
     }
     shell
 }
 
-pub fn get_packages() -> String {
-    let mut counts = Vec::new();
-
-    // pacman
-    if let Some(output) = run_cmd("pacman", &["-Qq"]) {
-        let count = String::from_utf8_lossy(&output.stdout).lines().count();
-        if count > 0 {
-            counts.push(format!("{} (pacman)", count));
-        }
-    }
-
-    // flatpak
-    if let Some(output) = run_cmd("flatpak", &["list", "--app"]) {
-        let count = String::from_utf8_lossy(&output.stdout).lines().count();
-        if count > 0 {
-            counts.push(format!("{} (flatpak)", count));
-        }
-    }
-
-    // snap
-    if let Some(output) = run_cmd("snap", &["list"]) {
-        let count = String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .skip(1)
-            .count();
-        if count > 0 {
-            counts.push(format!("{} (snap)", count));
-        }
-    }
-
-    // dpkg (debian/ubuntu)
-    if counts.is_empty()
-        && let Some(output) = run_cmd("dpkg-query", &["-f", ".\n", "-W"])
-    {
-        let count = String::from_utf8_lossy(&output.stdout).lines().count();
-        if count > 0 {
-            counts.push(format!("{} (dpkg)", count));
-        }
-    }
-
-    // rpm (fedora/rhel)
-    if counts.is_empty()
-        && let Some(output) = run_cmd("rpm", &["-qa"])
-    {
-        let count = String::from_utf8_lossy(&output.stdout).lines().count();
-        if count > 0 {
-            counts.push(format!("{} (rpm)", count));
-        }
-    }
-
-    if counts.is_empty() {
-        "Unknown".to_string()
-    } else {
-        counts.join(", ")
-    }
-}
-
 pub fn get_shell_theme() -> Option<String> {
     let home = std::env::var("HOME").ok()?;
     let shell = std::env::var("SHELL").ok()?;
@@ -221,7 +167,7 @@ pub fn get_shell_theme() -> Option<String> {
     None
 }
 
-pub fn get_editor() -> Option<String> {
+pub fn get_editor(cmd: &dyn CommandRunner) -> Option<String> {
     // Check EDITOR and VISUAL env vars
     let editor_var = std::env::var("EDITOR")
         .or_else(|_| std::env::var("VISUAL"))
@@ -233,7 +179,7 @@ pub fn get_editor() -> Option<String> {
         .unwrap_or(&editor_var);
 
     // Get version for known editors
-    let (cmd, display_name) = match editor_name {
+    let (program, display_name) = match editor_name {
         "nvim" | "neovim" => ("nvim", "Neovim"),
         "vim" => ("vim", "Vim"),
         "nano" => ("nano", "Nano"),
@@ -248,8 +194,7 @@ pub fn get_editor() -> Option<String> {
     };
 
     // Try to get version
-    if let Some(output) = run_cmd(cmd, &["--version"]) {
-        let version_out = String::from_utf8_lossy(&output.stdout);
+    if let Some(version_out) = cmd.run(program, &["--version"]) {
         let first_line = version_out.lines().next().unwrap_or("");
 
         // Parse version from output
@@ -0,0 +1,76 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Filesystem root that collectors read `/proc`, `/sys`, `/etc` and `/var`
+/// through.
+///
+/// Normally this is `/`. Pointing it at a captured tree (`--sysroot` or
+/// `GHOSTFETCH_SYSROOT`) lets the collectors run against another machine's
+/// files, which is how the fixture tests exercise hardware nobody has on hand.
+/// Paths under `$HOME` and the live process tree are not redirected.
+#[derive(Clone, Debug)]
+pub struct Sysroot {
+    root: PathBuf,
+}
+
+impl Default for Sysroot {
+    fn default() -> Self {
+        Sysroot::new("/")
+    }
+}
+
+impl Sysroot {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Sysroot { root: root.into() }
+    }
+
+    /// Whether this is the running system's own root, so live kernel
+    /// interfaces such as netlink describe the same machine as the files.
+    pub fn is_host(&self) -> bool {
+        self.root == Path::new("/")
+    }
+
+    /// Map an absolute system path like `/proc/uptime` under the root.
+    pub fn path(&self, path: impl AsRef<Path>) -> PathBuf {
+        let path = path.as_ref();
+        self.root.join(path.strip_prefix("/").unwrap_or(path))
+    }
+
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        fs::read_to_string(self.path(path))
+    }
+
+    /// List a directory. Entry paths include the root, so they can be read
+    /// with plain `std::fs` calls.
+    pub fn read_dir(&self, path: impl AsRef<Path>) -> io::Result<fs::ReadDir> {
+        fs::read_dir(self.path(path))
+    }
+
+    /// Read a sysfs-style single-value file, trimmed. Empty files count as
+    /// missing.
+    pub fn read_trimmed(&self, path: impl AsRef<Path>) -> Option<String> {
+        self.read_to_string(path)
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_joins_under_root() {
+        let root = Sysroot::new("/tmp/capture");
+        assert_eq!(
+            root.path("/proc/uptime"),
+            PathBuf::from("/tmp/capture/proc/uptime")
+        );
+        assert_eq!(
+            Sysroot::default().path("/etc/os-release"),
+            PathBuf::from("/etc/os-release")
+        );
+    }
+}
@@ -0,0 +1,778 @@
+//! Pending upgrades and the time of the last system upgrade, worked out
+//! offline from the package manager's synced repository data and logs.
+
+use super::packages::{NativeManager, native_managers};
+use super::sysroot::Sysroot;
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdatesInfo {
+    /// Tool the figures come from (`pacman`, `apt` or `dnf`).
+    pub manager: String,
+    /// Upgrades available according to the last repository sync. `None` when
+    /// the manager keeps no local copy ghostfetch can read.
+    pub pending: Option<usize>,
+    /// Unix time of the last upgrade.
+    pub last_upgrade: Option<i64>,
+}
+
+impl fmt::Display for UpdatesInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        match self.pending {
+            Some(0) => parts.push("Up to date".to_string()),
+            Some(n) => parts.push(format!("{} pending", n)),
+            None => {}
+        }
+        if let Some(at) = self.last_upgrade {
+            let ago = format_ago(now_secs() - at);
+            if parts.is_empty() {
+                parts.push(format!("Last upgrade {}", ago));
+            } else {
+                parts.push(format!("last upgrade {}", ago));
+            }
+        }
+        write!(f, "{} ({})", parts.join(", "), self.manager)
+    }
+}
+
+/// The newest version in the last repository sync of each package that was
+/// installed at the time, per manager. Reading the repositories is the slow
+/// part, so this is computed once per sync; packages installed since then
+/// came from that sync and are current.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UpdateCandidates {
+    pacman: Option<HashMap<String, String>>,
+    /// Keyed on `name:arch`.
+    apt: Option<HashMap<String, String>>,
+}
+
+/// Read the synced repository data of every native manager that keeps one.
+pub fn get_update_candidates(root: &Sysroot) -> UpdateCandidates {
+    let mut candidates = UpdateCandidates::default();
+    for manager in native_managers(root) {
+        match manager {
+            NativeManager::Pacman => {
+                candidates.pacman = pacman_candidates(root, &pacman_installed(root))
+            }
+            NativeManager::Dpkg => candidates.apt = apt_candidates(root, &dpkg_installed(root)),
+            NativeManager::Rpm => {}
+        }
+    }
+    candidates
+}
+
+/// Updates for the first native package manager that has anything to report.
+pub fn get_updates(root: &Sysroot, candidates: &UpdateCandidates) -> Option<UpdatesInfo> {
+    native_managers(root).into_iter().find_map(|manager| {
+        let info = match manager {
+            NativeManager::Pacman => UpdatesInfo {
+                manager: "pacman".to_string(),
+                pending: candidates.pacman.as_ref().map(|candidates| {
+                    count_pending(&pacman_installed(root), candidates, pacman_vercmp)
+                }),
+                last_upgrade: pacman_last_upgrade(root),
+            },
+            NativeManager::Dpkg => UpdatesInfo {
+                manager: "apt".to_string(),
+                pending: candidates.apt.as_ref().map(|candidates| {
+                    count_pending(&dpkg_installed(root), candidates, dpkg_vercmp)
+                }),
+                last_upgrade: dpkg_last_upgrade(root),
+            },
+            // dnf's repo cache is compressed XML keyed against rpm headers;
+            // only the history is cheap to read offline
+            NativeManager::Rpm => UpdatesInfo {
+                manager: "dnf".to_string(),
+                pending: None,
+                last_upgrade: dnf_last_upgrade(root),
+            },
+        };
+        (info.pending.is_some() || info.last_upgrade.is_some()).then_some(info)
+    })
+}
+
+/// Installed packages with a newer candidate.
+fn count_pending(
+    installed: &HashMap<String, String>,
+    candidates: &HashMap<String, String>,
+    vercmp: fn(&str, &str) -> Ordering,
+) -> usize {
+    installed
+        .iter()
+        .filter(|(name, version)| {
+            candidates
+                .get(*name)
+                .is_some_and(|new| vercmp(new, version) == Ordering::Greater)
+        })
+        .count()
+}
+
+/// Name and version of every package in the local database.
+fn pacman_installed(root: &Sysroot) -> HashMap<String, String> {
+    let mut installed = HashMap::new();
+    for entry in root
+        .read_dir("/var/lib/pacman/local")
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        if let Ok(desc) = fs::read_to_string(entry.path().join("desc"))
+            && let Some((name, version)) = parse_pacman_desc(&desc)
+        {
+            installed.insert(name, version);
+        }
+    }
+    installed
+}
+
+/// Versions of the `installed` packages in the sync databases from the last
+/// `pacman -Sy`, the way `checkupdates` reads a fresh copy. The first
+/// repository that has a package wins, as in pacman itself.
+fn pacman_candidates(
+    root: &Sysroot,
+    installed: &HashMap<String, String>,
+) -> Option<HashMap<String, String>> {
+    let mut candidates = HashMap::new();
+    let mut any_repo = false;
+    for repo in pacman_repos(root) {
+        let Ok(db) = fs::File::open(root.path(format!("/var/lib/pacman/sync/{}.db", repo))) else {
+            continue;
+        };
+        any_repo |= read_pacman_sync_db(db, &mut |name, version| {
+            if installed.contains_key(name) && !candidates.contains_key(name) {
+                candidates.insert(name.to_string(), version.to_string());
+            }
+        });
+    }
+    any_repo.then_some(candidates)
+}
+
+/// Repository names in `pacman.conf` order, or the sync databases on disk
+/// when the config can't be read.
+fn pacman_repos(root: &Sysroot) -> Vec<String> {
+    if let Ok(conf) = root.read_to_string("/etc/pacman.conf") {
+        return conf
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('[')?.strip_suffix(']'))
+            .filter(|section| *section != "options")
+            .map(str::to_string)
+            .collect();
+    }
+
+    let mut repos: Vec<String> = root
+        .read_dir("/var/lib/pacman/sync")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            name.strip_suffix(".db").map(str::to_string)
+        })
+        .collect();
+    repos.sort();
+    repos
+}
+
+/// Name and version from a pacman `desc` file.
+fn parse_pacman_desc(desc: &str) -> Option<(String, String)> {
+    let mut name = None;
+    let mut version = None;
+    let mut lines = desc.lines();
+    while let Some(line) = lines.next() {
+        match line {
+            "%NAME%" => name = lines.next(),
+            "%VERSION%" => version = lines.next(),
+            _ => {}
+        }
+    }
+    Some((name?.to_string(), version?.to_string()))
+}
+
+/// Call `visit(name, version)` for each entry of a sync database: a gzipped
+/// tar with a `<pkg>-<ver>/desc` per package. Returns whether the database
+/// could be read at all (e.g. not for a zstd database).
+fn read_pacman_sync_db(db: impl Read, visit: &mut dyn FnMut(&str, &str)) -> bool {
+    let mut archive = tar::Archive::new(GzDecoder::new(db));
+    let Ok(entries) = archive.entries() else {
+        return false;
+    };
+
+    let mut any = false;
+    let mut desc = String::new();
+    for mut entry in entries.flatten() {
+        any = true;
+        let is_desc = entry
+            .path()
+            .is_ok_and(|path| path.file_name().is_some_and(|name| name == "desc"));
+        desc.clear();
+        if is_desc
+            && entry.read_to_string(&mut desc).is_ok()
+            && let Some((name, version)) = parse_pacman_desc(&desc)
+        {
+            visit(&name, &version);
+        }
+    }
+    any
+}
+
+/// Start of the most recent `pacman -Syu` in `/var/log/pacman.log`.
+fn pacman_last_upgrade(root: &Sysroot) -> Option<i64> {
+    let log = root.read_to_string("/var/log/pacman.log").ok()?;
+    log.lines()
+        .rev()
+        .find(|line| line.contains("[PACMAN] starting full system upgrade"))
+        .and_then(|line| line.strip_prefix('[')?.split(']').next())
+        .and_then(parse_timestamp)
+}
+
+/// Installed packages from `/var/lib/dpkg/status`, keyed on `name:arch`.
+fn dpkg_installed(root: &Sysroot) -> HashMap<String, String> {
+    let mut installed = HashMap::new();
+    if let Ok(status) = fs::File::open(root.path("/var/lib/dpkg/status")) {
+        for_each_stanza(BufReader::new(status), &mut |stanza| {
+            let is_installed = stanza
+                .status
+                .as_deref()
+                .is_some_and(|status| status.ends_with(" installed"));
+            if is_installed && let Some(key) = stanza.key() {
+                installed.insert(key, stanza.version.clone());
+            }
+        });
+    }
+    installed
+}
+
+/// Newest version of each `installed` package in apt's downloaded
+/// `Packages` lists. Pinning and phased updates are not taken into account.
+fn apt_candidates(
+    root: &Sysroot,
+    installed: &HashMap<String, String>,
+) -> Option<HashMap<String, String>> {
+    let mut candidates: HashMap<String, String> = HashMap::new();
+    let mut any_list = false;
+    for entry in root.read_dir("/var/lib/apt/lists").ok()?.flatten() {
+        // Compressed lists (Acquire::GzipIndexes) are skipped
+        if !entry.file_name().to_string_lossy().ends_with("_Packages") {
+            continue;
+        }
+        let Ok(list) = fs::File::open(entry.path()) else {
+            continue;
+        };
+        any_list = true;
+        for_each_stanza(BufReader::new(list), &mut |stanza| {
+            let Some(key) = stanza.key().filter(|key| installed.contains_key(key)) else {
+                return;
+            };
+            match candidates.get_mut(&key) {
+                Some(best) if dpkg_vercmp(&stanza.version, best) == Ordering::Greater => {
+                    *best = stanza.version.clone();
+                }
+                Some(_) => {}
+                None => {
+                    candidates.insert(key, stanza.version.clone());
+                }
+            }
+        });
+    }
+    any_list.then_some(candidates)
+}
+
+/// The fields of a Debian control stanza that updates care about.
+#[derive(Default)]
+struct Stanza {
+    package: String,
+    architecture: String,
+    version: String,
+    status: Option<String>,
+}
+
+impl Stanza {
+    fn key(&self) -> Option<String> {
+        let complete =
+            !self.package.is_empty() && !self.architecture.is_empty() && !self.version.is_empty();
+        complete.then(|| format!("{}:{}", self.package, self.architecture))
+    }
+}
+
+/// Call `visit` for each stanza of a control file, reading it line by line
+/// so multi-megabyte lists are never held in memory. Continuation lines are
+/// skipped.
+fn for_each_stanza(reader: impl BufRead, visit: &mut dyn FnMut(&Stanza)) {
+    let mut stanza = Stanza::default();
+    let mut started = false;
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            if started {
+                visit(&stanza);
+            }
+            stanza = Stanza::default();
+            started = false;
+            continue;
+        }
+        started = true;
+        if line.starts_with([' ', '\t']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key {
+            "Package" => stanza.package = value,
+            "Architecture" => stanza.architecture = value,
+            "Version" => stanza.version = value,
+            "Status" => stanza.status = Some(value),
+            _ => {}
+        }
+    }
+    if started {
+        visit(&stanza);
+    }
+}
+
+/// Most recent package upgrade in `/var/log/dpkg.log`, checking the last
+/// rotated log too in case the current one was just rotated.
+fn dpkg_last_upgrade(root: &Sysroot) -> Option<i64> {
+    ["/var/log/dpkg.log", "/var/log/dpkg.log.1"]
+        .iter()
+        .filter_map(|path| root.read_to_string(path).ok())
+        .find_map(|log| {
+            // 2026-01-06 08:41:27 upgrade libc6:amd64 2.39-0ubuntu8.3 2.39-0ubuntu8.4
+            log.lines()
+                .rev()
+                .find(|line| line.split_whitespace().nth(2) == Some("upgrade"))
+                .and_then(|line| parse_timestamp(line.get(..19)?))
+        })
+}
+
+/// Most recent upgrade in the dnf logs: `dnf.rpm.log` for dnf 4 and
+/// `dnf5.log` for dnf 5.
+fn dnf_last_upgrade(root: &Sysroot) -> Option<i64> {
+    let dnf4 = root
+        .read_to_string("/var/log/dnf.rpm.log")
+        .ok()
+        .and_then(|log| {
+            // 2024-10-20T14:03:11+0000 SUBDEBUG Upgrade: bash-5.2.32-1.fc41.x86_64
+            log.lines()
+                .rev()
+                .find(|line| line.contains(" Upgrade: ") || line.contains(" Upgraded: "))
+                .and_then(|line| parse_timestamp(line.split_whitespace().next()?))
+        });
+
+    let dnf5 = root
+        .read_to_string("/var/log/dnf5.log")
+        .ok()
+        .and_then(|log| {
+            // 2026-01-03T09:12:44+0000 [2817] INFO --- DNF5 launched with arguments: "dnf upgrade" ---
+            log.lines()
+                .rev()
+                .find(|line| {
+                    line.split_once("launched with arguments:")
+                        .is_some_and(|(_, args)| {
+                            args.split(|c: char| c.is_whitespace() || c == '"')
+                                .any(|arg| matches!(arg, "upgrade" | "update" | "distro-sync"))
+                        })
+                })
+                .and_then(|line| parse_timestamp(line.split_whitespace().next()?))
+        });
+
+    dnf4.max(dnf5)
+}
+
+/// pacman's `vercmp`: compare `[epoch:]version[-release]` strings.
+fn pacman_vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (epoch_a, version_a, release_a) = split_evr(a);
+    let (epoch_b, version_b, release_b) = split_evr(b);
+
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(version_a, version_b))
+        .then_with(|| match (release_a, release_b) {
+            (Some(a), Some(b)) => rpmvercmp(a, b),
+            _ => Ordering::Equal,
+        })
+}
+
+fn split_evr(evr: &str) -> (&str, &str, Option<&str>) {
+    let (epoch, rest) = match evr.split_once(':') {
+        Some((epoch, rest)) if epoch.bytes().all(|b| b.is_ascii_digit()) => (epoch, rest),
+        _ => ("0", evr),
+    };
+    match rest.rsplit_once('-') {
+        Some((version, release)) => (epoch, version, Some(release)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// rpm/alpm segment comparison: alternating numeric and alphabetic runs,
+/// numbers compared by value, numbers newer than letters, and a trailing
+/// alphabetic run older than nothing (`1.0a < 1.0 < 1.0.1`).
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        let (sep_a, sep_b) = (i, j);
+        while i < a.len() && !a[i].is_ascii_alphanumeric() {
+            i += 1;
+        }
+        while j < b.len() && !b[j].is_ascii_alphanumeric() {
+            j += 1;
+        }
+        if i >= a.len() || j >= b.len() {
+            break;
+        }
+        // A longer separator sorts higher
+        if i - sep_a != j - sep_b {
+            return (i - sep_a).cmp(&(j - sep_b));
+        }
+
+        let numeric = a[i].is_ascii_digit();
+        let same_kind = |c: u8| {
+            if numeric {
+                c.is_ascii_digit()
+            } else {
+                c.is_ascii_alphabetic()
+            }
+        };
+        let (start_a, start_b) = (i, j);
+        while i < a.len() && same_kind(a[i]) {
+            i += 1;
+        }
+        while j < b.len() && same_kind(b[j]) {
+            j += 1;
+        }
+        let (seg_a, seg_b) = (&a[start_a..i], &b[start_b..j]);
+
+        // Different kinds: numeric is newer than alphabetic
+        if seg_b.is_empty() {
+            return if numeric {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let order = if numeric {
+            let trim = |s: &[u8]| -> usize { s.iter().take_while(|&&c| c == b'0').count() };
+            let (seg_a, seg_b) = (&seg_a[trim(seg_a)..], &seg_b[trim(seg_b)..]);
+            seg_a.len().cmp(&seg_b.len()).then_with(|| seg_a.cmp(seg_b))
+        } else {
+            seg_a.cmp(seg_b)
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+
+    let (rest_a, rest_b) = (a.get(i).copied(), b.get(j).copied());
+    match (rest_a, rest_b) {
+        (None, None) => Ordering::Equal,
+        // A remaining alphabetic run never beats running out
+        (None, Some(c)) if !c.is_ascii_alphabetic() => Ordering::Less,
+        (Some(c), _) if c.is_ascii_alphabetic() => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+/// Epoch, upstream version and Debian revision of a dpkg version.
+fn split_dpkg_version(version: &str) -> (u64, &str, &str) {
+    let (epoch, rest) = match version.split_once(':') {
+        Some((epoch, rest)) => (epoch.parse().unwrap_or(0), rest),
+        None => (0, version),
+    };
+    let (upstream, revision) = rest.rsplit_once('-').unwrap_or((rest, ""));
+    (epoch, upstream, revision)
+}
+
+/// dpkg's version comparison (`dpkg --compare-versions`).
+fn dpkg_vercmp(a: &str, b: &str) -> Ordering {
+    let (epoch_a, upstream_a, revision_a) = split_dpkg_version(a);
+    let (epoch_b, upstream_b, revision_b) = split_dpkg_version(b);
+
+    epoch_a
+        .cmp(&epoch_b)
+        .then_with(|| verrevcmp(upstream_a, upstream_b))
+        .then_with(|| verrevcmp(revision_a, revision_b))
+}
+
+/// Compare one dpkg version part: non-digit runs character by character
+/// (`~` before everything, even the end; letters before other symbols),
+/// then digit runs by value.
+fn verrevcmp(a: &str, b: &str) -> Ordering {
+    fn order(c: Option<u8>) -> i32 {
+        match c {
+            None => 0,
+            Some(c) if c.is_ascii_digit() => 0,
+            Some(c) if c.is_ascii_alphabetic() => c as i32,
+            Some(b'~') => -1,
+            Some(c) => c as i32 + 256,
+        }
+    }
+
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+            let (ac, bc) = (order(a.get(i).copied()), order(b.get(j).copied()));
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+            i += 1;
+            j += 1;
+        }
+        while i < a.len() && a[i] == b'0' {
+            i += 1;
+        }
+        while j < b.len() && b[j] == b'0' {
+            j += 1;
+        }
+        let mut first_diff = Ordering::Equal;
+        while i < a.len() && j < b.len() && a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            if first_diff == Ordering::Equal {
+                first_diff = a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+        if i < a.len() && a[i].is_ascii_digit() {
+            return Ordering::Greater;
+        }
+        if j < b.len() && b[j].is_ascii_digit() {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Parse a log timestamp: `2026-01-04T21:14:09+0100`, `...Z`, or a local
+/// time such as `2026-01-06 08:41:27` or `2019-03-01 10:22`.
+fn parse_timestamp(s: &str) -> Option<i64> {
+    let date = s.get(..10)?;
+    let mut ymd = date.split('-').map(|n| n.parse::<i64>().ok());
+    let (year, month, day) = (ymd.next()??, ymd.next()??, ymd.next()??);
+    if !matches!(s.get(10..11)?, "T" | " ") {
+        return None;
+    }
+
+    let rest = s.get(11..)?;
+    let split = rest.find(['+', '-', 'Z']).unwrap_or(rest.len());
+    let (time, zone) = rest.split_at(split);
+    let mut hms = time.split(':').map(|n| n.parse::<i64>().ok());
+    let (hour, minute) = (hms.next()??, hms.next()??);
+    let second = hms.next().flatten().unwrap_or(0);
+
+    let civil = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+    let offset = match zone {
+        "" => local_offset(civil),
+        "Z" => 0,
+        zone => {
+            let sign = if zone.starts_with('-') { -1 } else { 1 };
+            let digits: String = zone[1..].chars().filter(|c| c.is_ascii_digit()).collect();
+            let hours = digits.get(..2)?.parse::<i64>().ok()?;
+            let minutes = digits
+                .get(2..4)
+                .and_then(|m| m.parse::<i64>().ok())
+                .unwrap_or(0);
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+    Some(civil - offset)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The local timezone's UTC offset in seconds around `at`.
+fn local_offset(at: i64) -> i64 {
+    let time = at as libc::time_t;
+    // SAFETY: localtime_r only writes to the tm we pass in
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64
+}
+
+/// "5 mins ago", "3 hours ago", "12 days ago".
+pub(super) fn format_ago(secs: i64) -> String {
+    let plural = |n: i64, unit: &str| {
+        if n == 1 {
+            format!("1 {} ago", unit)
+        } else {
+            format!("{} {}s ago", n, unit)
+        }
+    };
+    match secs {
+        ..60 => "just now".to_string(),
+        60..3600 => plural(secs / 60, "min"),
+        3600..86400 => plural(secs / 3600, "hour"),
+        _ => plural(secs / 86400, "day"),
+    }
+}
+
+pub(super) fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+
+    #[test]
+    fn test_pacman_vercmp() {
+        use Ordering::*;
+        let cases = [
+            ("1.0a", "1.0", Less),
+            ("1.0", "1.0.1", Less),
+            ("1.0.a", "1.0", Greater),
+            ("1.0rc1", "1.0", Less),
+            ("1.10", "1.9", Greater),
+            ("1.001", "1.1", Equal),
+            ("6.18.4.arch1-1", "6.18.3.arch1-1", Greater),
+            ("1:25.3.1-1", "25.4.0-1", Greater),
+            ("2.42-1", "2.42-2", Less),
+            ("2.42", "2.42-2", Equal),
+            ("1.0_a", "1.0.a", Equal),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(pacman_vercmp(a, b), expected, "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_dpkg_vercmp() {
+        use Ordering::*;
+        let cases = [
+            ("1.0~rc1", "1.0", Less),
+            ("1.0", "1.0+dfsg", Less),
+            ("2.39-0ubuntu8.4", "2.39-0ubuntu8.3", Greater),
+            ("1:1.0", "2.0", Greater),
+            ("1.0-2ubuntu1.1", "1.0-2ubuntu1", Greater),
+            ("1.0a", "1.0+", Less),
+            ("1.000", "1.0", Equal),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(dpkg_vercmp(a, b), expected, "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_timestamp("2026-01-04T21:14:09+0100"),
+            Some(1767557649)
+        );
+        assert_eq!(
+            parse_timestamp("2026-01-04T20:14:09+00:00"),
+            Some(1767557649)
+        );
+        assert_eq!(
+            parse_timestamp("2026-01-04T15:14:09-0500"),
+            Some(1767557649)
+        );
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn test_format_ago() {
+        assert_eq!(format_ago(30), "just now");
+        assert_eq!(format_ago(60), "1 min ago");
+        assert_eq!(format_ago(3 * 3600 + 5), "3 hours ago");
+        assert_eq!(format_ago(12 * 86400), "12 days ago");
+    }
+
+    #[test]
+    fn test_get_updates_from_fixtures() {
+        let updates = |machine| {
+            let root = fixtures::root(machine);
+            get_updates(&root, &get_update_candidates(&root))
+        };
+        let arch = updates("arch-desktop").unwrap();
+        assert_eq!(arch.manager, "pacman");
+        assert_eq!(arch.pending, Some(6));
+        assert_eq!(arch.last_upgrade, Some(1767557649));
+
+        let ubuntu = updates("ubuntu-thinkpad").unwrap();
+        assert_eq!(ubuntu.manager, "apt");
+        assert_eq!(ubuntu.pending, Some(5));
+        assert!(ubuntu.last_upgrade.is_some());
+
+        let fedora = updates("fedora-vm").unwrap();
+        assert_eq!(fedora.manager, "dnf");
+        assert_eq!(fedora.pending, None);
+        assert_eq!(fedora.last_upgrade, Some(1767431564));
+
+        assert!(updates("void-laptop").is_none());
+    }
+
+    #[test]
+    fn test_for_each_stanza() {
+        let list = "Package: curl\nArchitecture: amd64\nVersion: 8.5.0-2ubuntu10.6\n\
+                    Description: command line tool\n multi-line description\n\n\n\
+                    Package: tzdata\nArchitecture: all\nVersion: 2025b-0ubuntu0.24.04\n\
+                    Status: install ok installed";
+        let mut keys = Vec::new();
+        for_each_stanza(list.as_bytes(), &mut |stanza| {
+            keys.push((stanza.key(), stanza.status.clone()));
+        });
+        assert_eq!(
+            keys,
+            [
+                (Some("curl:amd64".to_string()), None),
+                (
+                    Some("tzdata:all".to_string()),
+                    Some("install ok installed".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_updates_display() {
+        let info = UpdatesInfo {
+            manager: "pacman".to_string(),
+            pending: Some(6),
+            last_upgrade: None,
+        };
+        assert_eq!(info.to_string(), "6 pending (pacman)");
+
+        let info = UpdatesInfo {
+            manager: "dnf".to_string(),
+            pending: None,
+            last_upgrade: Some(now_secs() - 2 * 86400),
+        };
+        assert_eq!(info.to_string(), "Last upgrade 2 days ago (dnf)");
+    }
+}
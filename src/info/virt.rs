@@ -0,0 +1,387 @@
+//! Whether we run inside a virtual machine, a container or an application
+//! sandbox. Everything is read from files, so a sysroot capture is detected
+//! the same way as the live system.
+
+use super::sysroot::Sysroot;
+use serde::Serialize;
+use std::fmt;
+
+const DMI: &str = "/sys/devices/virtual/dmi/id";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Hypervisor {
+    Kvm,
+    Qemu,
+    VMware,
+    HyperV,
+    VirtualBox,
+    Xen,
+    /// The CPU reports a hypervisor but DMI doesn't say which.
+    Unknown,
+}
+
+impl Hypervisor {
+    fn name(self) -> &'static str {
+        match self {
+            Hypervisor::Kvm => "KVM",
+            Hypervisor::Qemu => "QEMU",
+            Hypervisor::VMware => "VMware",
+            Hypervisor::HyperV => "Hyper-V",
+            Hypervisor::VirtualBox => "VirtualBox",
+            Hypervisor::Xen => "Xen",
+            Hypervisor::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Container {
+    Docker,
+    Podman,
+    Lxc,
+    Nspawn,
+    Wsl,
+    /// A `container=` value we don't know, e.g. `oci`.
+    Other,
+}
+
+impl Container {
+    fn name(self) -> &'static str {
+        match self {
+            Container::Docker => "Docker",
+            Container::Podman => "Podman",
+            Container::Lxc => "LXC",
+            Container::Nspawn => "systemd-nspawn",
+            Container::Wsl => "WSL",
+            Container::Other => "unknown",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sandbox {
+    Flatpak,
+    Snap,
+}
+
+impl Sandbox {
+    fn name(self) -> &'static str {
+        match self {
+            Sandbox::Flatpak => "Flatpak",
+            Sandbox::Snap => "Snap",
+        }
+    }
+}
+
+/// Each layer we found, innermost first when displayed. A container on a VM
+/// has both set; DMI and cpuinfo are not namespaced, so they describe the
+/// machine under the container.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct VirtInfo {
+    pub hypervisor: Option<Hypervisor>,
+    pub container: Option<Container>,
+    pub sandbox: Option<Sandbox>,
+}
+
+impl VirtInfo {
+    pub fn is_empty(&self) -> bool {
+        self.hypervisor.is_none() && self.container.is_none() && self.sandbox.is_none()
+    }
+
+    /// The Host line: the DMI model followed by what it runs in, or only the
+    /// latter when there is no model, e.g. in a container without DMI.
+    pub fn describe_host(&self, model: Option<String>) -> Option<String> {
+        if self.is_empty() {
+            return model;
+        }
+        Some(match model {
+            Some(model) => format!("{} - {}", model, self),
+            None => self.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for VirtInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut layers = Vec::new();
+        if let Some(sandbox) = self.sandbox {
+            layers.push(format!("{} sandbox", sandbox.name()));
+        }
+        match self.container {
+            Some(Container::Wsl) => layers.push("WSL".to_string()),
+            Some(Container::Other) => layers.push("container".to_string()),
+            Some(container) => layers.push(format!("{} container", container.name())),
+            None => {}
+        }
+        // WSL always runs on Hyper-V; saying so adds nothing
+        if self.container != Some(Container::Wsl) {
+            match self.hypervisor {
+                Some(Hypervisor::Unknown) => layers.push("virtual machine".to_string()),
+                Some(hypervisor) => layers.push(format!("{} virtual machine", hypervisor.name())),
+                None => {}
+            }
+        }
+        write!(f, "{}", layers.join(" in "))
+    }
+}
+
+pub fn get_virtualization(root: &Sysroot) -> VirtInfo {
+    VirtInfo {
+        hypervisor: detect_hypervisor(root),
+        container: detect_container(root),
+        sandbox: detect_sandbox(root),
+    }
+}
+
+fn detect_hypervisor(root: &Sysroot) -> Option<Hypervisor> {
+    let dmi = |field: &str| {
+        root.read_trimmed(format!("{}/{}", DMI, field))
+            .unwrap_or_default()
+    };
+    let from_dmi = hypervisor_from_dmi(
+        &dmi("sys_vendor"),
+        &dmi("product_name"),
+        &dmi("bios_vendor"),
+    );
+    match from_dmi {
+        // QEMU's DMI is the same with and without KVM acceleration
+        Some(Hypervisor::Qemu) if kvm_clock(root) => Some(Hypervisor::Kvm),
+        Some(hypervisor) => Some(hypervisor),
+        // Xen PV guests have no DMI at all
+        None if root.read_trimmed("/sys/hypervisor/type").as_deref() == Some("xen") => {
+            Some(Hypervisor::Xen)
+        }
+        None if cpu_has_hypervisor_flag(root) => Some(Hypervisor::Unknown),
+        None => None,
+    }
+}
+
+fn hypervisor_from_dmi(sys_vendor: &str, product: &str, bios_vendor: &str) -> Option<Hypervisor> {
+    if product == "KVM" || sys_vendor == "KVM" {
+        Some(Hypervisor::Kvm)
+    } else if sys_vendor == "QEMU" || bios_vendor.starts_with("QEMU") {
+        Some(Hypervisor::Qemu)
+    } else if sys_vendor.starts_with("VMware") || product.starts_with("VMware") {
+        Some(Hypervisor::VMware)
+    } else if product == "VirtualBox" || sys_vendor == "innotek GmbH" {
+        Some(Hypervisor::VirtualBox)
+    } else if sys_vendor == "Microsoft Corporation" && product == "Virtual Machine" {
+        Some(Hypervisor::HyperV)
+    } else if sys_vendor == "Xen" || bios_vendor == "Xen" {
+        Some(Hypervisor::Xen)
+    } else {
+        None
+    }
+}
+
+fn kvm_clock(root: &Sysroot) -> bool {
+    root.read_to_string("/sys/devices/system/clocksource/clocksource0/available_clocksource")
+        .is_ok_and(|sources| sources.split_whitespace().any(|s| s == "kvm-clock"))
+}
+
+fn cpu_has_hypervisor_flag(root: &Sysroot) -> bool {
+    root.read_to_string("/proc/cpuinfo").is_ok_and(|cpuinfo| {
+        cpuinfo
+            .lines()
+            .filter(|line| line.starts_with("flags"))
+            .take(1)
+            .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor"))
+    })
+}
+
+fn detect_container(root: &Sysroot) -> Option<Container> {
+    if root.path("/.dockerenv").exists() {
+        return Some(Container::Docker);
+    }
+    if root.path("/run/.containerenv").exists() {
+        return Some(Container::Podman);
+    }
+    // systemd copies PID 1's `container=` here; environ itself is root-only
+    let from_env = root
+        .read_trimmed("/run/systemd/container")
+        .or_else(|| environ_var(root, "/proc/1/environ", "container"));
+    if let Some(value) = from_env.filter(|value| !value.is_empty()) {
+        return Some(match value.as_str() {
+            "docker" => Container::Docker,
+            "podman" => Container::Podman,
+            "systemd-nspawn" => Container::Nspawn,
+            "wsl" => Container::Wsl,
+            value if value.starts_with("lxc") => Container::Lxc,
+            _ => Container::Other,
+        });
+    }
+    if let Some(container) = container_from_cgroup(root) {
+        return Some(container);
+    }
+
+    let osrelease = root
+        .read_trimmed("/proc/sys/kernel/osrelease")
+        .unwrap_or_default()
+        .to_lowercase();
+    if osrelease.contains("microsoft") || root.path("/proc/sys/fs/binfmt_misc/WSLInterop").exists()
+    {
+        return Some(Container::Wsl);
+    }
+    None
+}
+
+/// Only cgroup v1 and hosts without cgroup namespaces show the container's
+/// path; under v2 it is usually just `0::/`.
+fn container_from_cgroup(root: &Sysroot) -> Option<Container> {
+    let cgroup = root.read_to_string("/proc/1/cgroup").ok()?;
+    cgroup.lines().find_map(|line| {
+        let path = line.splitn(3, ':').nth(2)?;
+        if path.contains("/docker/") || path.contains("/docker-") {
+            Some(Container::Docker)
+        } else if path.contains("libpod") {
+            Some(Container::Podman)
+        } else if path.contains("/lxc/") || path.contains("lxc.payload") {
+            Some(Container::Lxc)
+        } else {
+            None
+        }
+    })
+}
+
+fn detect_sandbox(root: &Sysroot) -> Option<Sandbox> {
+    if root.path("/.flatpak-info").exists()
+        || environ_var(root, "/proc/self/environ", "FLATPAK_ID").is_some()
+    {
+        Some(Sandbox::Flatpak)
+    } else if environ_var(root, "/proc/self/environ", "SNAP_NAME").is_some() {
+        Some(Sandbox::Snap)
+    } else {
+        None
+    }
+}
+
+/// One variable from a NUL-separated `environ` file.
+fn environ_var(root: &Sysroot, path: &str, name: &str) -> Option<String> {
+    let environ = root.read_to_string(path).ok()?;
+    environ.split('\0').find_map(|entry| {
+        let (key, value) = entry.split_once('=')?;
+        (key == name).then(|| value.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+    use std::fs;
+
+    #[test]
+    fn test_fixture_machines() {
+        let vm = get_virtualization(&fixtures::root("fedora-vm"));
+        assert_eq!(vm.hypervisor, Some(Hypervisor::Kvm));
+        assert_eq!(vm.container, None);
+        assert_eq!(vm.to_string(), "KVM virtual machine");
+
+        let docker = get_virtualization(&fixtures::root("alpine-docker"));
+        assert_eq!(docker.container, Some(Container::Docker));
+        assert_eq!(
+            docker.describe_host(None).as_deref(),
+            Some("Docker container")
+        );
+
+        let desktop = get_virtualization(&fixtures::root("arch-desktop"));
+        assert!(desktop.is_empty());
+        assert_eq!(
+            desktop.describe_host(Some("ROG".to_string())).as_deref(),
+            Some("ROG")
+        );
+    }
+
+    #[test]
+    fn test_hypervisor_from_dmi() {
+        let cases = [
+            (
+                "QEMU",
+                "Standard PC (Q35 + ICH9, 2009)",
+                "SeaBIOS",
+                Some(Hypervisor::Qemu),
+            ),
+            ("Red Hat", "KVM", "SeaBIOS", Some(Hypervisor::Kvm)),
+            (
+                "VMware, Inc.",
+                "VMware20,1",
+                "VMware, Inc.",
+                Some(Hypervisor::VMware),
+            ),
+            (
+                "innotek GmbH",
+                "VirtualBox",
+                "innotek GmbH",
+                Some(Hypervisor::VirtualBox),
+            ),
+            (
+                "Microsoft Corporation",
+                "Virtual Machine",
+                "Microsoft Corporation",
+                Some(Hypervisor::HyperV),
+            ),
+            ("Xen", "HVM domU", "Xen", Some(Hypervisor::Xen)),
+            (
+                "Microsoft Corporation",
+                "Surface Laptop 5",
+                "Microsoft Corporation",
+                None,
+            ),
+            ("LENOVO", "21KCCTO1WW", "LENOVO", None),
+        ];
+        for (vendor, product, bios, expected) in cases {
+            assert_eq!(
+                hypervisor_from_dmi(vendor, product, bios),
+                expected,
+                "{}",
+                product
+            );
+        }
+    }
+
+    #[test]
+    fn test_containers_and_sandboxes() {
+        let dir = std::env::temp_dir().join(format!("ghostfetch-virt-{}", std::process::id()));
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path.trim_start_matches('/'));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        let root = Sysroot::new(dir.clone());
+
+        write("/proc/1/cgroup", "12:pids:/lxc.payload.web/init.scope\n");
+        assert_eq!(detect_container(&root), Some(Container::Lxc));
+
+        write("/run/systemd/container", "systemd-nspawn\n");
+        assert_eq!(detect_container(&root), Some(Container::Nspawn));
+
+        write("/run/.containerenv", "engine=\"podman-5.3.1\"\n");
+        assert_eq!(detect_container(&root), Some(Container::Podman));
+
+        write("/proc/self/environ", "HOME=/root\0SNAP_NAME=ghostfetch\0");
+        assert_eq!(detect_sandbox(&root), Some(Sandbox::Snap));
+        write("/.flatpak-info", "[Application]\nname=org.example.App\n");
+        assert_eq!(detect_sandbox(&root), Some(Sandbox::Flatpak));
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        // WSL2 reports a hypervisor without DMI; it is implied by WSL
+        write(
+            "/proc/sys/kernel/osrelease",
+            "5.15.167.4-microsoft-standard-WSL2\n",
+        );
+        write(
+            "/proc/cpuinfo",
+            "processor\t: 0\nflags\t\t: fpu vme hypervisor\n",
+        );
+        let wsl = get_virtualization(&root);
+        assert_eq!(wsl.container, Some(Container::Wsl));
+        assert_eq!(wsl.hypervisor, Some(Hypervisor::Unknown));
+        assert_eq!(wsl.to_string(), "WSL");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
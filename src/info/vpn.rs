@@ -0,0 +1,400 @@
+//! VPN and overlay network links: WireGuard, Tailscale, OpenVPN, ZeroTier
+//! and other tun/tap devices.
+
+use super::network::{InterfaceInfo, LinkKind, get_network};
+use super::runner::CommandRunner;
+use super::sysroot::Sysroot;
+use super::updates::{format_ago, now_secs};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+/// tailscaled's LocalAPI socket; `/var/run` is a symlink to `/run` on most
+/// distributions but not all.
+const TAILSCALE_SOCKETS: &[&str] = &[
+    "/run/tailscale/tailscaled.sock",
+    "/var/run/tailscale/tailscaled.sock",
+];
+const LOCAL_API_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VpnKind {
+    WireGuard,
+    Tailscale,
+    OpenVpn,
+    ZeroTier,
+    /// A tun or tap device nothing more is known about.
+    Tun,
+}
+
+impl VpnKind {
+    fn name(self) -> &'static str {
+        match self {
+            VpnKind::WireGuard => "WireGuard",
+            VpnKind::Tailscale => "Tailscale",
+            VpnKind::OpenVpn => "OpenVPN",
+            VpnKind::ZeroTier => "ZeroTier",
+            VpnKind::Tun => "tun",
+        }
+    }
+}
+
+/// One active VPN or overlay link.
+#[derive(Clone, Debug, Serialize)]
+pub struct VpnInfo {
+    pub kind: VpnKind,
+    /// `None` for Tailscale in userspace networking mode.
+    pub interface: Option<String>,
+    /// Addresses in CIDR notation, or the node's Tailscale IPs.
+    pub addresses: Vec<String>,
+    pub peers: Option<usize>,
+    /// Tailscale peers currently online.
+    pub peers_online: Option<usize>,
+    /// Unix time of the most recent WireGuard handshake with any peer.
+    pub last_handshake: Option<i64>,
+    /// Tailnet name, e.g. `example.com` or `alice@example.com`.
+    pub tailnet: Option<String>,
+    /// tailscaled's backend state when it isn't `Running`, e.g. `NeedsLogin`.
+    pub state: Option<String>,
+}
+
+impl VpnInfo {
+    fn new(kind: VpnKind, iface: Option<&InterfaceInfo>) -> Self {
+        VpnInfo {
+            kind,
+            interface: iface.map(|iface| iface.name.clone()),
+            addresses: iface
+                .map(|iface| iface.ipv4.iter().chain(&iface.ipv6).cloned().collect())
+                .unwrap_or_default(),
+            peers: None,
+            peers_online: None,
+            last_handshake: None,
+            tailnet: None,
+            state: None,
+        }
+    }
+}
+
+impl fmt::Display for VpnInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind.name())?;
+        if let Some(ref interface) = self.interface {
+            write!(f, " ({})", interface)?;
+        }
+        if !self.addresses.is_empty() {
+            write!(f, ": {}", self.addresses.join(", "))?;
+        }
+
+        let mut extras = Vec::new();
+        extras.extend(self.state.clone());
+        extras.extend(
+            self.tailnet
+                .as_ref()
+                .map(|name| format!("tailnet {}", name)),
+        );
+        match (self.peers_online, self.peers) {
+            (Some(online), Some(total)) => {
+                extras.push(format!("{}/{} peers online", online, total))
+            }
+            (None, Some(1)) => extras.push("1 peer".to_string()),
+            (None, Some(total)) => extras.push(format!("{} peers", total)),
+            _ => {}
+        }
+        if let Some(at) = self.last_handshake {
+            extras.push(format!("handshake {}", format_ago(now_secs() - at)));
+        }
+        if !extras.is_empty() {
+            write!(f, " ({})", extras.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Active VPN links, in interface order, followed by Tailscale when it runs
+/// without an interface.
+pub fn get_vpns(root: &Sysroot, cmd: &dyn CommandRunner) -> Vec<VpnInfo> {
+    let network = get_network(root);
+    let tailscale = tailscale_status(root);
+    let openvpn_running = process_running(root, "openvpn");
+
+    let mut vpns = Vec::new();
+    let mut tailscale_shown = false;
+    for iface in &network.interfaces {
+        if iface.state.as_deref() == Some("down") {
+            continue;
+        }
+        let kind = match iface.kind {
+            LinkKind::WireGuard => VpnKind::WireGuard,
+            LinkKind::Tun if is_tailscale(iface, tailscale.as_ref()) => VpnKind::Tailscale,
+            // wireguard-go and boringtun create a tun plus a UAPI socket
+            LinkKind::Tun
+                if root
+                    .path(format!("/run/wireguard/{}.sock", iface.name))
+                    .exists() =>
+            {
+                VpnKind::WireGuard
+            }
+            // ZeroTier names its taps zt<network id hash>
+            LinkKind::Tun if iface.name.starts_with("zt") => VpnKind::ZeroTier,
+            LinkKind::Tun if openvpn_running => VpnKind::OpenVpn,
+            LinkKind::Tun => VpnKind::Tun,
+            _ => continue,
+        };
+
+        let mut vpn = VpnInfo::new(kind, Some(iface));
+        match kind {
+            VpnKind::WireGuard => fill_wireguard(cmd, &mut vpn),
+            VpnKind::Tailscale => {
+                if let Some(ref status) = tailscale {
+                    fill_tailscale(status, &mut vpn);
+                }
+                tailscale_shown = true;
+            }
+            _ => {}
+        }
+        vpns.push(vpn);
+    }
+
+    if !tailscale_shown && let Some(ref status) = tailscale {
+        let mut vpn = VpnInfo::new(VpnKind::Tailscale, None);
+        fill_tailscale(status, &mut vpn);
+        vpns.push(vpn);
+    }
+    vpns
+}
+
+/// Tailscale's tun is `tailscale0` unless renamed with `--tun`; then it is
+/// recognized by carrying one of the node's Tailscale IPs.
+fn is_tailscale(iface: &InterfaceInfo, status: Option<&TailscaleStatus>) -> bool {
+    if iface.name.starts_with("tailscale") {
+        return true;
+    }
+    let Some(status) = status else {
+        return false;
+    };
+    iface.ipv4.iter().chain(&iface.ipv6).any(|cidr| {
+        let ip = cidr.split('/').next().unwrap_or(cidr);
+        status.self_node.tailscale_ips.iter().any(|own| own == ip)
+    })
+}
+
+/// Peer count and latest handshake from `wg show <iface> dump`. The kernel
+/// only hands these out with CAP_NET_ADMIN, so as a normal user they stay
+/// unknown.
+fn fill_wireguard(cmd: &dyn CommandRunner, vpn: &mut VpnInfo) {
+    let Some(ref interface) = vpn.interface else {
+        return;
+    };
+    if let Some(dump) = cmd.run("wg", &["show", interface, "dump"]) {
+        let (peers, last_handshake) = parse_wg_dump(&dump);
+        vpn.peers = Some(peers);
+        vpn.last_handshake = last_handshake;
+    }
+}
+
+/// `(peer count, latest handshake)`. The first line describes the interface;
+/// each following line is a peer whose fifth field is the last handshake in
+/// Unix time, 0 if there never was one.
+fn parse_wg_dump(dump: &str) -> (usize, Option<i64>) {
+    let peers: Vec<&str> = dump.lines().skip(1).filter(|l| !l.is_empty()).collect();
+    let last_handshake = peers
+        .iter()
+        .filter_map(|line| line.split('\t').nth(4)?.parse::<i64>().ok())
+        .filter(|&at| at > 0)
+        .max();
+    (peers.len(), last_handshake)
+}
+
+#[derive(Debug, Deserialize)]
+struct TailscaleStatus {
+    #[serde(rename = "BackendState")]
+    backend_state: String,
+    #[serde(rename = "Self")]
+    self_node: TailscaleNode,
+    #[serde(rename = "CurrentTailnet")]
+    current_tailnet: Option<TailscaleTailnet>,
+    #[serde(rename = "Peer", default)]
+    peers: Option<HashMap<String, TailscaleNode>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TailscaleNode {
+    #[serde(rename = "TailscaleIPs", default)]
+    tailscale_ips: Vec<String>,
+    #[serde(rename = "Online", default)]
+    online: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TailscaleTailnet {
+    #[serde(rename = "Name")]
+    name: String,
+}
+
+fn fill_tailscale(status: &TailscaleStatus, vpn: &mut VpnInfo) {
+    if !status.self_node.tailscale_ips.is_empty() {
+        vpn.addresses = status.self_node.tailscale_ips.clone();
+    }
+    vpn.tailnet = status.current_tailnet.as_ref().map(|t| t.name.clone());
+    let peers = status.peers.as_ref();
+    vpn.peers = Some(peers.map_or(0, HashMap::len));
+    vpn.peers_online = Some(peers.map_or(0, |p| p.values().filter(|n| n.online).count()));
+    if status.backend_state != "Running" {
+        vpn.state = Some(status.backend_state.clone());
+    }
+}
+
+/// `GET /localapi/v0/status` on tailscaled's socket. Any user may read it.
+fn tailscale_status(root: &Sysroot) -> Option<TailscaleStatus> {
+    let body = TAILSCALE_SOCKETS
+        .iter()
+        .find_map(|socket| local_api_get(&root.path(socket), "/localapi/v0/status"))?;
+    serde_json::from_str(&body).ok()
+}
+
+/// A plain HTTP/1.0 request over a Unix socket, so the body arrives unchunked
+/// and ends when the server closes the connection.
+fn local_api_get(socket: &Path, path: &str) -> Option<String> {
+    let mut stream = UnixStream::connect(socket).ok()?;
+    stream.set_read_timeout(Some(LOCAL_API_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(LOCAL_API_TIMEOUT)).ok()?;
+    // tailscaled rejects requests without this Host as a DNS rebinding guard
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: local-tailscaled.sock\r\n\r\n",
+        path
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok()?;
+    let (head, body) = response.split_once("\r\n\r\n")?;
+    let status = head.lines().next()?.split_whitespace().nth(1)?;
+    (status == "200").then(|| body.to_string())
+}
+
+/// Whether any process's `comm` is `name`.
+fn process_running(root: &Sysroot, name: &str) -> bool {
+    let Ok(entries) = root.read_dir("/proc") else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let is_pid = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|pid| pid.bytes().all(|b| b.is_ascii_digit()));
+        is_pid
+            && std::fs::read_to_string(entry.path().join("comm"))
+                .is_ok_and(|comm| comm.trim() == name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::runner::fixtures;
+    use std::fs;
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    #[test]
+    fn test_wireguard_with_peers() {
+        let machine = "arch-desktop";
+        let vpns = get_vpns(&fixtures::root(machine), &fixtures::commands(machine));
+        assert_eq!(vpns.len(), 1);
+        let wg = &vpns[0];
+        assert_eq!(wg.kind, VpnKind::WireGuard);
+        assert_eq!(wg.interface.as_deref(), Some("wg0"));
+        assert_eq!(wg.addresses, ["10.8.0.2/24"]);
+        // The second peer never completed a handshake
+        assert_eq!(wg.peers, Some(2));
+        assert_eq!(wg.last_handshake, Some(1760700000));
+    }
+
+    #[test]
+    fn test_tun_kinds() {
+        let tailscale = get_vpns(
+            &fixtures::root("nixos-framework"),
+            &fixtures::commands("nixos-framework"),
+        );
+        assert_eq!(tailscale[0].kind, VpnKind::Tailscale);
+        assert_eq!(
+            tailscale[0].to_string(),
+            "Tailscale (tailscale0): 100.101.102.103/32, fd7a:115c:a1e0::6f01:2a3b/128"
+        );
+
+        let openvpn = get_vpns(
+            &fixtures::root("ubuntu-thinkpad"),
+            &fixtures::commands("ubuntu-thinkpad"),
+        );
+        assert_eq!(openvpn[0].to_string(), "OpenVPN (tun0): 10.8.8.6/24");
+
+        let zerotier = get_vpns(
+            &fixtures::root("void-laptop"),
+            &fixtures::commands("void-laptop"),
+        );
+        assert_eq!(zerotier[0].kind, VpnKind::ZeroTier);
+
+        assert!(
+            get_vpns(
+                &fixtures::root("fedora-vm"),
+                &fixtures::commands("fedora-vm")
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_tailscale_local_api() {
+        let dir = std::env::temp_dir().join(format!("ghostfetch-ts-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("run/tailscale")).unwrap();
+        let listener = UnixListener::bind(dir.join("run/tailscale/tailscaled.sock")).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 512];
+            let n = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..n]).into_owned();
+            let body = r#"{
+                "BackendState": "Running",
+                "Self": {"HostName": "ghost", "TailscaleIPs": ["100.101.102.103", "fd7a:115c:a1e0::6f01:2a3b"], "Online": true},
+                "CurrentTailnet": {"Name": "example.com", "MagicDNSSuffix": "tail1234.ts.net"},
+                "Peer": {
+                    "nodekey:aa": {"TailscaleIPs": ["100.64.0.2"], "Online": true},
+                    "nodekey:bb": {"TailscaleIPs": ["100.64.0.3"], "Online": false}
+                }
+            }"#;
+            write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
+                body
+            )
+            .unwrap();
+            request
+        });
+
+        // No tailscale0 in this root: userspace networking mode
+        let vpns = get_vpns(&Sysroot::new(&dir), &fixtures::commands("fedora-vm"));
+        let request = server.join().unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(request.starts_with("GET /localapi/v0/status HTTP/1.0\r\n"));
+        assert!(request.contains("Host: local-tailscaled.sock"));
+        assert_eq!(vpns.len(), 1);
+        assert_eq!(
+            vpns[0].to_string(),
+            "Tailscale: 100.101.102.103, fd7a:115c:a1e0::6f01:2a3b (tailnet example.com, 1/2 peers online)"
+        );
+    }
+
+    #[test]
+    fn test_parse_wg_dump_without_peers() {
+        let dump = "cHJpdmF0ZQ==\tcHVibGlj\t51820\toff\n";
+        assert_eq!(parse_wg_dump(dump), (0, None));
+    }
+}
@@ -1,12 +1,22 @@
+mod cache;
+mod config;
 mod info;
 mod logos;
+mod modules;
 mod types;
 
-use clap::Parser;
-use colored::Colorize;
+use cache::{Cache, CacheMode};
+use clap::{Parser, ValueEnum};
+use colored::{ColoredString, Colorize};
+use config::Config;
+use info::{CommandRunner, RecordingRunner, ReplayRunner, Sysroot, SystemRunner};
+use modules::{Context, InfoModule, Outcome};
 use std::borrow::Cow;
 use std::io::{self, IsTerminal};
-use sysinfo::System;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use types::SystemInfo;
 
 fn get_terminal_width() -> usize {
     // Try to get terminal size
@@ -112,11 +122,7 @@ fn strip_ansi_len(s: &str) -> usize {
     len
 }
 
-fn validate_ascii_file(path: &str) -> Result<String, String> {
-    use std::path::Path;
-
-    let path = Path::new(path);
-
+fn validate_ascii_file(path: &Path) -> Result<String, String> {
     if !path.exists() {
         return Err(format!("ASCII file not found: {}", path.display()));
     }
@@ -163,6 +169,46 @@ struct Args {
     /// Show all available info (including optional fields)
     #[arg(long)]
     all: bool,
+
+    /// Output format
+    #[arg(long, short = 'f', value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Overall time budget for collecting info, in milliseconds
+    #[arg(long, short = 't', value_name = "MS")]
+    timeout: Option<u64>,
+
+    /// Read /proc, /sys and /etc from this directory instead of /
+    #[arg(long, value_name = "DIR", env = "GHOSTFETCH_SYSROOT")]
+    sysroot: Option<PathBuf>,
+
+    /// Don't read or write the cache of slow fields
+    #[arg(long, conflicts_with = "refresh_cache")]
+    no_cache: bool,
+
+    /// Recompute the cached fields this run shows
+    #[arg(long)]
+    refresh_cache: bool,
+
+    /// Use a specific config file instead of ~/.config/ghostfetch/config.toml
+    #[arg(long, short = 'c', value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Save the output of every helper command into this directory
+    #[arg(long, value_name = "DIR", conflicts_with = "replay_commands")]
+    record_commands: Option<PathBuf>,
+
+    /// Answer helper commands from outputs saved with --record-commands
+    #[arg(long, value_name = "DIR")]
+    replay_commands: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// Logo and colored info lines
+    Text,
+    /// Machine-readable JSON
+    Json,
 }
 
 fn print_color_blocks() {
@@ -183,210 +229,184 @@ fn print_color_blocks() {
     println!();
 }
 
-fn main() {
-    let args = Args::parse();
-
-    // Handle --no-color
-    if args.no_color {
-        colored::control::set_override(false);
+fn collect_info(
+    ctx: &Arc<Context>,
+    modules: &[Arc<dyn InfoModule>],
+    budget: Duration,
+) -> SystemInfo {
+    let (_, distro_id) = info::get_os_info(&ctx.root);
+    let outcomes = modules::collect_all(Arc::clone(ctx), modules, budget);
+
+    SystemInfo {
+        username: info::get_username(),
+        hostname: info::get_hostname(&ctx.root),
+        distro_id,
+        modules: modules.iter().map(|m| m.key()).zip(outcomes).collect(),
     }
+}
 
-    let mut sys = System::new_all();
-    sys.refresh_all();
-
-    let (os_name, distro_id) = info::get_os_info();
-
-    // Get logo based on args
-    let logo = if args.off {
-        None
-    } else if let Some(ref logo_name) = args.logo {
-        Some(logos::get_logo(logo_name))
-    } else if let Some(ref ascii_path) = args.ascii {
-        // Custom ASCII file support with validation
-        match validate_ascii_file(ascii_path) {
-            Ok(content) => Some(logos::DistroLogo {
-                art: Cow::Owned(content),
-                width: 40,
-                primary_color: |s| s.cyan(),
-            }),
-            Err(e) => {
-                eprintln!("Warning: {}", e);
-                Some(logos::get_logo(&distro_id))
-            }
-        }
-    } else {
-        Some(logos::get_logo(&distro_id))
-    };
-
-    let username = info::get_username();
-    let hostname = info::get_hostname();
-
-    // Build info lines
+fn build_info_lines(
+    sys_info: &SystemInfo,
+    modules: &[Arc<dyn InfoModule>],
+    config: &Config,
+    paint: &dyn Fn(&str) -> ColoredString,
+) -> Vec<String> {
     let mut info_lines: Vec<String> = Vec::new();
-
-    let primary_color: fn(&str) -> colored::ColoredString = logo
-        .as_ref()
-        .map(|l| l.primary_color)
-        .unwrap_or(|s| s.cyan());
+    let username = &sys_info.username;
+    let hostname = &sys_info.hostname;
 
     // Title
     info_lines.push(format!(
         "{}{}{}",
-        primary_color(&username).bold(),
+        paint(username).bold(),
         "@".white(),
-        primary_color(&hostname).bold()
+        paint(hostname).bold()
     ));
     info_lines.push("-".repeat(username.len() + 1 + hostname.len()).to_string());
 
-    // Host (motherboard/laptop model)
-    if let Some(host) = info::get_host() {
-        info_lines.push(format!("{:<12} {}", primary_color("Host").bold(), host));
-    }
-
-    // System info
-    info_lines.push(format!("{:<12} {}", primary_color("OS").bold(), os_name));
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("Kernel").bold(),
-        info::get_kernel()
-    ));
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("Uptime").bold(),
-        info::get_uptime()
-    ));
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("Packages").bold(),
-        info::get_packages()
-    ));
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("Shell").bold(),
-        info::get_shell()
-    ));
-
-    // Shell theme (p10k, starship, omz, etc.)
-    if let Some(theme) = info::get_shell_theme() {
-        info_lines.push(format!("{:<12} {}", primary_color("Prompt").bold(), theme));
-    }
-
-    // Display/Monitor info
-    let monitors = info::get_monitors();
-    for (i, monitor) in monitors.iter().enumerate() {
-        let label = if monitors.len() > 1 {
-            format!("Display {}", i + 1)
-        } else {
-            "Display".to_string()
+    // Modules are collected in the same order they are listed
+    for (module, (_, outcome)) in modules.iter().zip(&sys_info.modules) {
+        let label = config.label(module.key(), module.label());
+        let value = match outcome {
+            Outcome::Ready(Some(value)) => value,
+            Outcome::Ready(None) => continue,
+            Outcome::TimedOut => {
+                info_lines.push(format!(
+                    "{:<12} {}",
+                    paint(label).bold(),
+                    modules::TIMED_OUT_PLACEHOLDER.dimmed()
+                ));
+                continue;
+            }
         };
-
-        let mut display_str = format!(
-            "({}) {} @ {}",
-            monitor.name, monitor.resolution, monitor.refresh_rate
-        );
-        if monitor.hdr {
-            display_str.push_str(" [HDR]");
+        for (label, text) in value.lines(label) {
+            info_lines.push(format!("{:<12} {}", paint(&label).bold(), text));
         }
-
-        info_lines.push(format!(
-            "{:<12} {}",
-            primary_color(&label).bold(),
-            display_str
-        ));
     }
 
-    // DE and WM
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("DE").bold(),
-        info::get_de()
-    ));
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("WM").bold(),
-        info::get_wm()
-    ));
+    // Empty line before color blocks
+    info_lines.push(String::new());
 
-    // Terminal
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("Terminal").bold(),
-        info::get_terminal()
-    ));
+    info_lines
+}
 
-    // Terminal Font
-    if let Some(font) = info::get_terminal_font() {
-        info_lines.push(format!("{:<12} {}", primary_color("Font").bold(), font));
+/// Resolve the logo from CLI flags first, then the config file, then the
+/// detected distro.
+fn select_logo(args: &Args, config: &Config, distro_id: &str) -> Option<logos::DistroLogo> {
+    if args.off {
+        return None;
     }
 
-    // Multiplexer (tmux, zellij, screen)
-    if let Some(mux) = info::get_multiplexer() {
-        info_lines.push(format!(
-            "{:<12} {}",
-            primary_color("Multiplexer").bold(),
-            mux
-        ));
-    }
+    let ascii_path = if let Some(ref logo_name) = args.logo {
+        return Some(logos::get_logo(logo_name));
+    } else if let Some(ref ascii_path) = args.ascii {
+        PathBuf::from(ascii_path)
+    } else if !config.logo.enabled {
+        return None;
+    } else if let Some(ref logo_name) = config.logo.name {
+        return Some(logos::get_logo(logo_name));
+    } else if let Some(ref ascii_path) = config.logo.ascii {
+        config::expand_home(ascii_path)
+    } else {
+        return Some(logos::get_logo(distro_id));
+    };
 
-    // Editor
-    if let Some(editor) = info::get_editor() {
-        info_lines.push(format!("{:<12} {}", primary_color("Editor").bold(), editor));
+    // Custom ASCII file support with validation
+    match validate_ascii_file(&ascii_path) {
+        Ok(content) => Some(logos::DistroLogo {
+            art: Cow::Owned(content),
+            width: 40,
+            primary_color: |s| s.cyan(),
+        }),
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            Some(logos::get_logo(distro_id))
+        }
     }
+}
 
-    // Hardware
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("CPU").bold(),
-        info::get_cpu(&sys)
-    ));
+fn main() {
+    let args = Args::parse();
+    let config = Config::load_or_default(args.config.as_deref());
 
-    // GPUs
-    let gpus = info::get_gpu();
-    for (i, gpu) in gpus.iter().enumerate() {
-        let label = if gpus.len() > 1 {
-            format!("GPU {}", i + 1)
-        } else {
-            "GPU".to_string()
-        };
-        info_lines.push(format!("{:<12} {}", primary_color(&label).bold(), gpu));
+    // Handle --no-color
+    if args.no_color || config.no_color {
+        colored::control::set_override(false);
     }
 
-    // Memory
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("Memory").bold(),
-        info::get_memory(&sys)
-    ));
-
-    // Swap (if exists)
-    if let Some(swap) = info::get_swap(&sys) {
-        info_lines.push(format!("{:<12} {}", primary_color("Swap").bold(), swap));
-    }
+    let json = args.format == OutputFormat::Json;
+    let all = args.all || config.all;
 
-    // Disks
-    let disks = info::get_disks();
-    for disk in &disks {
-        info_lines.push(format!("{:<12} {}", primary_color("Disk").bold(), disk));
+    let root = args.sysroot.clone().map(Sysroot::new).unwrap_or_default();
+    // A sysroot describes another machine; its values must not end up in
+    // this machine's cache
+    let cache_mode = if args.no_cache || !root.is_host() {
+        CacheMode::Disabled
+    } else if args.refresh_cache {
+        CacheMode::Refresh
+    } else {
+        CacheMode::Normal
+    };
+    let cmd: Box<dyn CommandRunner> = match (&args.record_commands, &args.replay_commands) {
+        (Some(dir), _) => Box::new(RecordingRunner::new(dir)),
+        (_, Some(dir)) => Box::new(ReplayRunner::new(dir)),
+        _ => Box::new(SystemRunner),
+    };
+    // JSON always carries the optional fields so its schema doesn't depend on --all
+    let modules = modules::select_modules(config.modules.as_deref(), all || json);
+    // Modules see the resolved list, so one can leave out what another shows
+    let ctx = Arc::new(Context::new(
+        Cache::open(cache_mode),
+        root,
+        cmd,
+        Config {
+            all,
+            modules: Some(modules.iter().map(|m| m.key().to_string()).collect()),
+            ..config.clone()
+        },
+    ));
+    let budget = args
+        .timeout
+        .or(config.timeout_ms)
+        .map(Duration::from_millis)
+        .unwrap_or(modules::DEFAULT_TIMEOUT);
+    let sys_info = collect_info(&ctx, &modules, budget);
+    ctx.cache.save();
+
+    if json {
+        match serde_json::to_string_pretty(&sys_info) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Error: cannot serialize system info: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
     }
 
-    // Network info
-    info_lines.push(format!(
-        "{:<12} {}",
-        primary_color("Local IP").bold(),
-        info::get_local_ip()
-    ));
+    let logo = select_logo(&args, &config, &sys_info.distro_id);
 
-    // Locale (optional, show with --all)
-    if args.all {
-        info_lines.push(format!(
-            "{:<12} {}",
-            primary_color("Locale").bold(),
-            info::get_locale()
-        ));
-    }
+    let logo_color = config.colors.logo.as_deref().and_then(config::parse_color);
+    let label_color = config
+        .colors
+        .labels
+        .as_deref()
+        .and_then(config::parse_color);
+    let primary_color: fn(&str) -> ColoredString = logo
+        .as_ref()
+        .map(|l| l.primary_color)
+        .unwrap_or(|s| s.cyan());
+    let paint_logo = |s: &str| match logo_color {
+        Some(color) => s.color(color),
+        None => primary_color(s),
+    };
+    let paint_label = |s: &str| match label_color.or(logo_color) {
+        Some(color) => s.color(color),
+        None => primary_color(s),
+    };
 
-    // Empty line before color blocks
-    info_lines.push(String::new());
+    // Build info lines
+    let info_lines = build_info_lines(&sys_info, &modules, &config, &paint_label);
 
     // Print output
     let term_width = get_terminal_width();
@@ -409,11 +429,7 @@ fn main() {
             // Truncate info line if too long
             let display_line = truncate_line(info_line, max_info_width);
 
-            print!(
-                "{:width$}",
-                (logo.primary_color)(logo_line),
-                width = logo.width
-            );
+            print!("{:width$}", paint_logo(logo_line), width = logo.width);
             println!("{}", display_line);
         }
 
@@ -483,7 +499,7 @@ mod tests {
 
     #[test]
     fn test_validate_ascii_file_nonexistent() {
-        let result = validate_ascii_file("/nonexistent/path");
+        let result = validate_ascii_file(Path::new("/nonexistent/path"));
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not found"));
     }
@@ -492,7 +508,7 @@ mod tests {
     fn test_validate_ascii_file_rejects_osc() {
         // This test would need a temp file with OSC sequences
         // For now, just verify the function exists and returns Result
-        let result = validate_ascii_file("/etc/passwd");
+        let result = validate_ascii_file(Path::new("/etc/passwd"));
         // /etc/passwd exists and should be valid (no bad sequences)
         assert!(result.is_ok() || result.is_err()); // Either is valid behavior
     }
@@ -0,0 +1,634 @@
+//! The built-in modules, one per `info::get_*` collector.
+
+use super::{Context, InfoModule, Value};
+use crate::cache;
+use crate::info;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Hardware facts (GPUs, DMI strings, RAM speed) can't change without a
+/// reboot, so they are keyed on the boot id and kept for a week.
+const HARDWARE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Version strings are keyed on the binary's path and mtime.
+const VERSION_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Package counts are keyed on the package databases' mtimes; the TTL only
+/// bounds how stale a count can get if a database is missed.
+const PACKAGES_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The public address is keyed on the endpoint and the local address, so
+/// moving networks refetches it; the TTL covers a changing address behind
+/// the same router.
+const PUBLIC_IP_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Package database paths whose mtime changes when packages are installed or
+/// removed.
+const PACKAGE_DBS: &[&str] = &[
+    "/var/lib/pacman/local",
+    "/var/lib/dpkg/status",
+    "/var/lib/rpm",
+    // Appending to the write-ahead log doesn't touch the directory's mtime
+    "/var/lib/rpm/rpmdb.sqlite-wal",
+    "/var/lib/flatpak/app",
+    "/var/lib/snapd/snaps",
+    "/snap",
+    "/lib/apk/db/installed",
+    "/var/db/xbps",
+    "/var/db/pkg",
+    "/nix/var/nix/profiles",
+    "/home/linuxbrew/.linuxbrew/Cellar",
+];
+
+/// Paths whose mtime changes on a repository sync, an upgrade, or a new log
+/// entry.
+const UPDATE_SOURCES: &[&str] = &[
+    "/var/lib/pacman/local",
+    "/var/lib/pacman/sync",
+    "/var/log/pacman.log",
+    "/var/lib/dpkg/status",
+    "/var/lib/apt/lists",
+    "/var/log/dpkg.log",
+    "/var/lib/rpm",
+    "/var/log/dnf.rpm.log",
+    "/var/log/dnf5.log",
+];
+
+/// Repository data replaced by a sync (`pacman -Sy`, `apt update`). Reading
+/// it is the slow part of Updates, so it is only redone when these change.
+const SYNC_DIRS: &[&str] = &["/var/lib/pacman/sync", "/var/lib/apt/lists"];
+
+/// The same for per-user installs, relative to `$HOME`.
+const HOME_PACKAGE_DBS: &[&str] = &[
+    ".local/share/flatpak/app",
+    ".local/state/nix/profiles",
+    ".linuxbrew/Cellar",
+    "Applications",
+    ".cargo/.crates2.json",
+    ".local/share/pipx/venvs",
+];
+
+/// Every built-in module, in the default display order.
+pub fn default_modules() -> Vec<Arc<dyn InfoModule>> {
+    vec![
+        Arc::new(Host),
+        Arc::new(Virtualization),
+        Arc::new(Os),
+        Arc::new(Kernel),
+        Arc::new(Uptime),
+        Arc::new(Packages),
+        Arc::new(Updates),
+        Arc::new(Shell),
+        Arc::new(Prompt),
+        Arc::new(Display),
+        Arc::new(De),
+        Arc::new(Wm),
+        Arc::new(Terminal),
+        Arc::new(Font),
+        Arc::new(Multiplexer),
+        Arc::new(Editor),
+        Arc::new(Cpu),
+        Arc::new(Gpu),
+        Arc::new(Sensors),
+        Arc::new(Memory),
+        Arc::new(Swap),
+        Arc::new(Disk),
+        Arc::new(Drives),
+        Arc::new(Battery),
+        Arc::new(LocalIp),
+        Arc::new(PublicIp),
+        Arc::new(Vpn),
+        Arc::new(Network),
+        Arc::new(Locale),
+    ]
+}
+
+pub struct Host;
+
+impl InfoModule for Host {
+    fn key(&self) -> &'static str {
+        "host"
+    }
+    fn label(&self) -> &'static str {
+        "Host"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let model =
+            ctx.cache
+                .get_or_insert_with("host", HARDWARE_TTL, &cache::boot_key(&ctx.root), || {
+                    info::get_host(&ctx.root)
+                });
+        // The Virt line already says what this runs in
+        if ctx.config.lists_module("virtualization") {
+            return model.map(Value::Text);
+        }
+        // Not cached: a container shares the boot id with its host
+        info::get_virtualization(&ctx.root)
+            .describe_host(model)
+            .map(Value::Text)
+    }
+}
+
+pub struct Virtualization;
+
+impl InfoModule for Virtualization {
+    fn key(&self) -> &'static str {
+        "virtualization"
+    }
+    fn label(&self) -> &'static str {
+        "Virt"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let virt = info::get_virtualization(&ctx.root);
+        (!virt.is_empty()).then_some(Value::Virtualization(virt))
+    }
+    fn optional(&self) -> bool {
+        true
+    }
+}
+
+pub struct Os;
+
+impl InfoModule for Os {
+    fn key(&self) -> &'static str {
+        "os_name"
+    }
+    fn label(&self) -> &'static str {
+        "OS"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        Some(Value::Text(info::get_os_info(&ctx.root).0))
+    }
+}
+
+pub struct Kernel;
+
+impl InfoModule for Kernel {
+    fn key(&self) -> &'static str {
+        "kernel"
+    }
+    fn label(&self) -> &'static str {
+        "Kernel"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        Some(Value::Text(info::get_kernel(&ctx.root)))
+    }
+}
+
+pub struct Uptime;
+
+impl InfoModule for Uptime {
+    fn key(&self) -> &'static str {
+        "uptime"
+    }
+    fn label(&self) -> &'static str {
+        "Uptime"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        Some(Value::Text(info::get_uptime(&ctx.root)))
+    }
+}
+
+pub struct Packages;
+
+impl InfoModule for Packages {
+    fn key(&self) -> &'static str {
+        "packages"
+    }
+    fn label(&self) -> &'static str {
+        "Packages"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let mut dbs: Vec<PathBuf> = PACKAGE_DBS.iter().map(|p| ctx.root.path(p)).collect();
+        if let Some(home) = &home {
+            dbs.extend(HOME_PACKAGE_DBS.iter().map(|p| home.join(p)));
+        }
+        let key = cache::mtime_key(&dbs);
+        Some(Value::Text(ctx.cache.get_or_insert_with(
+            "packages",
+            PACKAGES_TTL,
+            &key,
+            || info::get_packages(&ctx.root, home.as_deref(), &*ctx.cmd),
+        )))
+    }
+}
+
+pub struct Updates;
+
+impl InfoModule for Updates {
+    fn key(&self) -> &'static str {
+        "updates"
+    }
+    fn label(&self) -> &'static str {
+        "Updates"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let sources: Vec<PathBuf> = UPDATE_SOURCES.iter().map(|p| ctx.root.path(p)).collect();
+        let sync: Vec<PathBuf> = SYNC_DIRS.iter().map(|p| ctx.root.path(p)).collect();
+        ctx.cache
+            .get_or_insert_with("updates", PACKAGES_TTL, &cache::mtime_key(&sources), || {
+                let candidates = ctx.cache.get_or_insert_with(
+                    "update_candidates",
+                    PACKAGES_TTL,
+                    &cache::mtime_key(&sync),
+                    || info::get_update_candidates(&ctx.root),
+                );
+                info::get_updates(&ctx.root, &candidates)
+            })
+            .map(Value::Updates)
+    }
+}
+
+pub struct Shell;
+
+impl InfoModule for Shell {
+    fn key(&self) -> &'static str {
+        "shell"
+    }
+    fn label(&self) -> &'static str {
+        "Shell"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let key = cache::binary_key(&std::env::var("SHELL").unwrap_or_default());
+        Some(Value::Text(ctx.cache.get_or_insert_with(
+            "shell",
+            VERSION_TTL,
+            &key,
+            || info::get_shell(&*ctx.cmd),
+        )))
+    }
+}
+
+/// Shell prompt theme (p10k, starship, omz, etc.)
+pub struct Prompt;
+
+impl InfoModule for Prompt {
+    fn key(&self) -> &'static str {
+        "shell_theme"
+    }
+    fn label(&self) -> &'static str {
+        "Prompt"
+    }
+    fn collect(&self, _ctx: &Context) -> Option<Value> {
+        info::get_shell_theme().map(Value::Text)
+    }
+}
+
+pub struct Display;
+
+impl InfoModule for Display {
+    fn key(&self) -> &'static str {
+        "monitors"
+    }
+    fn label(&self) -> &'static str {
+        "Display"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let wm = info::get_wm(&*ctx.cmd);
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        Some(Value::Monitors(info::get_monitors(
+            &ctx.root,
+            &*ctx.cmd,
+            &wm,
+            home.as_deref(),
+        )))
+    }
+}
+
+pub struct De;
+
+impl InfoModule for De {
+    fn key(&self) -> &'static str {
+        "de"
+    }
+    fn label(&self) -> &'static str {
+        "DE"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        Some(Value::Text(info::get_de(&*ctx.cmd)))
+    }
+}
+
+pub struct Wm;
+
+impl InfoModule for Wm {
+    fn key(&self) -> &'static str {
+        "wm"
+    }
+    fn label(&self) -> &'static str {
+        "WM"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        Some(Value::Text(info::get_wm(&*ctx.cmd)))
+    }
+}
+
+pub struct Terminal;
+
+impl InfoModule for Terminal {
+    fn key(&self) -> &'static str {
+        "terminal"
+    }
+    fn label(&self) -> &'static str {
+        "Terminal"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        // Without a known terminal the answer is just $TERM, which is cheap
+        let Some(terminal) = info::find_terminal() else {
+            return Some(Value::Text(info::get_terminal(&*ctx.cmd)));
+        };
+        Some(Value::Text(ctx.cache.get_or_insert_with(
+            "terminal",
+            VERSION_TTL,
+            &cache::binary_key(terminal),
+            || info::get_terminal(&*ctx.cmd),
+        )))
+    }
+}
+
+pub struct Font;
+
+impl InfoModule for Font {
+    fn key(&self) -> &'static str {
+        "terminal_font"
+    }
+    fn label(&self) -> &'static str {
+        "Font"
+    }
+    fn collect(&self, _ctx: &Context) -> Option<Value> {
+        info::get_terminal_font().map(Value::Text)
+    }
+}
+
+/// Terminal multiplexer (tmux, zellij, screen)
+pub struct Multiplexer;
+
+impl InfoModule for Multiplexer {
+    fn key(&self) -> &'static str {
+        "multiplexer"
+    }
+    fn label(&self) -> &'static str {
+        "Multiplexer"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        info::get_multiplexer(&*ctx.cmd).map(Value::Text)
+    }
+}
+
+pub struct Editor;
+
+impl InfoModule for Editor {
+    fn key(&self) -> &'static str {
+        "editor"
+    }
+    fn label(&self) -> &'static str {
+        "Editor"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let editor = std::env::var("EDITOR")
+            .or_else(|_| std::env::var("VISUAL"))
+            .ok()?;
+        ctx.cache
+            .get_or_insert_with("editor", VERSION_TTL, &cache::binary_key(&editor), || {
+                info::get_editor(&*ctx.cmd)
+            })
+            .map(Value::Text)
+    }
+}
+
+pub struct Cpu;
+
+impl InfoModule for Cpu {
+    fn key(&self) -> &'static str {
+        "cpu"
+    }
+    fn label(&self) -> &'static str {
+        "CPU"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let Some(mut cpu) = info::get_cpu(&ctx.root, &ctx.sys) else {
+            return Some(Value::Text("Unknown".to_string()));
+        };
+        info::fill_cpu_details(&ctx.root, &mut cpu, &ctx.config.cpu);
+        Some(Value::Cpu(cpu))
+    }
+}
+
+pub struct Gpu;
+
+impl InfoModule for Gpu {
+    fn key(&self) -> &'static str {
+        "gpus"
+    }
+    fn label(&self) -> &'static str {
+        "GPU"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let mut gpus =
+            ctx.cache
+                .get_or_insert_with("gpus", HARDWARE_TTL, &cache::boot_key(&ctx.root), || {
+                    info::get_gpu(&ctx.root, &*ctx.cmd)
+                });
+        info::fill_gpu_details(&ctx.root, &*ctx.cmd, &mut gpus, &ctx.config.gpu);
+        Some(Value::Gpus(gpus))
+    }
+}
+
+pub struct Sensors;
+
+impl InfoModule for Sensors {
+    fn key(&self) -> &'static str {
+        "sensors"
+    }
+    fn label(&self) -> &'static str {
+        "Sensors"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let mut sensors = info::get_sensors(&ctx.root)?;
+        sensors.show_all = ctx.config.all;
+        Some(Value::Sensors(sensors))
+    }
+}
+
+pub struct Memory;
+
+impl InfoModule for Memory {
+    fn key(&self) -> &'static str {
+        "memory"
+    }
+    fn label(&self) -> &'static str {
+        "Memory"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let mut memory = info::get_memory(&ctx.root, &ctx.sys);
+        let layout = ctx.cache.get_or_insert_with(
+            "memory_layout",
+            HARDWARE_TTL,
+            &cache::boot_key(&ctx.root),
+            || info::get_memory_layout(&ctx.root, &*ctx.cmd),
+        );
+        memory.speed_mts = layout.as_ref().and_then(|layout| layout.speed_mts());
+        if ctx.config.memory.detail {
+            memory.layout = layout;
+        }
+        Some(Value::Memory(memory))
+    }
+}
+
+pub struct Swap;
+
+impl InfoModule for Swap {
+    fn key(&self) -> &'static str {
+        "swap"
+    }
+    fn label(&self) -> &'static str {
+        "Swap"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        info::get_swap(&ctx.root, &ctx.sys).map(Value::Memory)
+    }
+}
+
+pub struct Disk;
+
+impl InfoModule for Disk {
+    fn key(&self) -> &'static str {
+        "disks"
+    }
+    fn label(&self) -> &'static str {
+        "Disk"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        // Usage comes from statfs on the live mounts, which a sysroot can't
+        // redirect; showing the host's disks next to its data would mislead
+        if !ctx.root.is_host() {
+            return None;
+        }
+        Some(Value::Disks(info::get_disks(&ctx.config.disks)))
+    }
+}
+
+pub struct Drives;
+
+impl InfoModule for Drives {
+    fn key(&self) -> &'static str {
+        "drives"
+    }
+    fn label(&self) -> &'static str {
+        "Drive"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let drives = info::get_drives(&ctx.root);
+        (!drives.is_empty()).then_some(Value::Drives(drives))
+    }
+    fn optional(&self) -> bool {
+        true
+    }
+}
+
+pub struct Battery;
+
+impl InfoModule for Battery {
+    fn key(&self) -> &'static str {
+        "battery"
+    }
+    fn label(&self) -> &'static str {
+        "Battery"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        info::get_power(&ctx.root).map(Value::Power)
+    }
+}
+
+pub struct LocalIp;
+
+impl InfoModule for LocalIp {
+    fn key(&self) -> &'static str {
+        "local_ip"
+    }
+    fn label(&self) -> &'static str {
+        "Local IP"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        Some(Value::Text(info::get_local_ip(&ctx.root)))
+    }
+}
+
+pub struct PublicIp;
+
+impl InfoModule for PublicIp {
+    fn key(&self) -> &'static str {
+        "public_ip"
+    }
+    fn label(&self) -> &'static str {
+        "Public IP"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let config = &ctx.config.public_ip;
+        if !config.enabled {
+            return None;
+        }
+        // An offline lookup is `None`, which the cache never stores, so the
+        // next run asks again instead of showing nothing for an hour
+        let key = format!("{}@{}", config.url, info::get_local_ip(&ctx.root));
+        ctx.cache
+            .get_or_insert_with("public_ip", PUBLIC_IP_TTL, &key, || {
+                info::get_public_ip(&*ctx.cmd, config)
+            })
+            .map(Value::PublicIp)
+    }
+}
+
+pub struct Vpn;
+
+impl InfoModule for Vpn {
+    fn key(&self) -> &'static str {
+        "vpn"
+    }
+    fn label(&self) -> &'static str {
+        "VPN"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let vpns = info::get_vpns(&ctx.root, &*ctx.cmd);
+        (!vpns.is_empty()).then_some(Value::Vpns(vpns))
+    }
+}
+
+pub struct Network;
+
+impl InfoModule for Network {
+    fn key(&self) -> &'static str {
+        "network"
+    }
+    fn label(&self) -> &'static str {
+        "Network"
+    }
+    fn collect(&self, ctx: &Context) -> Option<Value> {
+        let mut network = info::get_network(&ctx.root);
+        info::fill_wifi_details(&ctx.root, &*ctx.cmd, &mut network);
+        let empty = network.active().next().is_none() && network.dns_servers.is_empty();
+        (!empty).then_some(Value::Network(network))
+    }
+    fn optional(&self) -> bool {
+        true
+    }
+}
+
+pub struct Locale;
+
+impl InfoModule for Locale {
+    fn key(&self) -> &'static str {
+        "locale"
+    }
+    fn label(&self) -> &'static str {
+        "Locale"
+    }
+    fn collect(&self, _ctx: &Context) -> Option<Value> {
+        Some(Value::Text(info::get_locale()))
+    }
+    fn optional(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,535 @@
+//! Info modules: the unit of collection and rendering.
+//!
+//! Every line ghostfetch prints comes from an [`InfoModule`]. A module collects
+//! a structured [`Value`] and carries a default label; the renderer walks an
+//! ordered module list and never needs to know what a module measures.
+
+mod builtin;
+
+use crate::cache::{self, Cache};
+use crate::config::Config;
+use crate::info::{
+    CommandRunner, CpuInfo, DiskInfo, DriveInfo, GpuInfo, MemoryInfo, MonitorInfo, NetworkInfo,
+    PowerInfo, PublicIpInfo, SensorsInfo, Sysroot, UpdatesInfo, VirtInfo, VpnInfo, helpers,
+};
+use serde::{Serialize, Serializer};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::System;
+
+pub use builtin::default_modules;
+
+/// Default overall collection budget.
+///
+/// Matches the per-command cap in `helpers`, so a single slow helper behaves
+/// the same as before while a pile of them no longer adds up.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Text shown in place of a module that missed the deadline.
+pub const TIMED_OUT_PLACEHOLDER: &str = "(timed out)";
+
+/// Shared state handed to every module during collection.
+pub struct Context {
+    pub sys: System,
+    pub cache: Cache,
+    pub root: Sysroot,
+    /// Runs external helper commands (real, recording or replaying).
+    pub cmd: Box<dyn CommandRunner>,
+    /// User settings that shape what modules collect.
+    pub config: Config,
+}
+
+impl Context {
+    pub fn new(cache: Cache, root: Sysroot, cmd: Box<dyn CommandRunner>, config: Config) -> Self {
+        let mut sys = System::new_all();
+        sys.refresh_all();
+        Context {
+            sys,
+            cache,
+            root,
+            cmd,
+            config,
+        }
+    }
+}
+
+/// A collected module value.
+///
+/// Serializes untagged, so text values become JSON strings and structured
+/// values become objects or arrays.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Text(String),
+    Cpu(CpuInfo),
+    Monitors(Vec<MonitorInfo>),
+    Gpus(Vec<GpuInfo>),
+    Memory(MemoryInfo),
+    Disks(Vec<DiskInfo>),
+    Drives(Vec<DriveInfo>),
+    Updates(UpdatesInfo),
+    Power(PowerInfo),
+    Network(NetworkInfo),
+    PublicIp(PublicIpInfo),
+    Vpns(Vec<VpnInfo>),
+    Virtualization(VirtInfo),
+    Sensors(SensorsInfo),
+}
+
+impl Value {
+    /// Render the value as `(label, text)` pairs, one per output line.
+    ///
+    /// List values get numbered labels ("GPU 1", "GPU 2") when there is more
+    /// than one entry; disks keep the plain label since each line names its
+    /// mount point.
+    pub fn lines(&self, label: &str) -> Vec<(String, String)> {
+        match self {
+            Value::Text(text) => vec![(label.to_string(), text.clone())],
+            Value::Cpu(cpu) => vec![(label.to_string(), cpu.to_string())],
+            Value::Memory(mem) => {
+                let mut lines = vec![(label.to_string(), mem.to_string())];
+                if let Some(ref layout) = mem.layout {
+                    let populated = match layout.slots {
+                        Some(slots) => {
+                            format!("{} of {} slots populated", layout.modules.len(), slots)
+                        }
+                        None => format!("{} modules", layout.modules.len()),
+                    };
+                    lines.push((label.to_string(), populated));
+                    lines.extend(
+                        layout
+                            .modules
+                            .iter()
+                            .map(|module| (label.to_string(), module.to_string())),
+                    );
+                }
+                lines
+            }
+            Value::Updates(updates) => vec![(label.to_string(), updates.to_string())],
+            Value::Monitors(monitors) => numbered(label, monitors),
+            Value::Gpus(gpus) if gpus.is_empty() => {
+                vec![(label.to_string(), "Unknown".to_string())]
+            }
+            Value::Gpus(gpus) => numbered(label, gpus),
+            Value::Power(power) => {
+                let mut lines = numbered(label, &power.batteries);
+                lines.extend(power.summary().map(|summary| (label.to_string(), summary)));
+                lines
+            }
+            Value::Sensors(sensors) if sensors.show_all => sensors
+                .temperatures
+                .iter()
+                .map(|t| t.to_string())
+                .chain(sensors.fans.iter().map(|f| f.to_string()))
+                .map(|text| (label.to_string(), text))
+                .collect(),
+            Value::Sensors(sensors) => vec![(label.to_string(), sensors.summary())],
+            Value::Network(network) => network
+                .active()
+                .map(|iface| iface.to_string())
+                .chain(network.dns_summary())
+                .map(|text| (label.to_string(), text))
+                .collect(),
+            Value::PublicIp(ip) => vec![(label.to_string(), ip.to_string())],
+            Value::Vpns(vpns) => numbered(label, vpns),
+            Value::Virtualization(virt) => vec![(label.to_string(), virt.to_string())],
+            Value::Drives(drives) => numbered(label, drives),
+            Value::Disks(disks) => disks
+                .iter()
+                .map(|d| (label.to_string(), d.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// The result of running one module.
+#[derive(Debug)]
+pub enum Outcome {
+    /// The module finished; `None` means it had nothing to show.
+    Ready(Option<Value>),
+    /// The module was still running when the deadline passed.
+    TimedOut,
+}
+
+impl Outcome {
+    pub fn is_timed_out(&self) -> bool {
+        matches!(self, Outcome::TimedOut)
+    }
+}
+
+impl Serialize for Outcome {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Outcome::Ready(value) => value.serialize(serializer),
+            Outcome::TimedOut => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Run every module concurrently and wait at most `budget` for all of them.
+///
+/// Each module gets its own thread. Results come back in module order; any
+/// module still running at the deadline is reported as [`Outcome::TimedOut`],
+/// its thread is left to finish in the background, and helper processes it
+/// spawned are killed so nothing outlives the program.
+pub fn collect_all(
+    ctx: Arc<Context>,
+    modules: &[Arc<dyn InfoModule>],
+    budget: Duration,
+) -> Vec<Outcome> {
+    let deadline = Instant::now() + budget;
+    let (tx, rx) = mpsc::channel();
+
+    for (index, module) in modules.iter().enumerate() {
+        let worker_tx = tx.clone();
+        let worker_ctx = Arc::clone(&ctx);
+        let worker_module = Arc::clone(module);
+        let spawned = thread::Builder::new()
+            .name(format!("module-{}", module.key()))
+            .spawn(move || {
+                cache::set_owner(worker_module.key());
+                let _ = worker_tx.send((index, worker_module.collect(&worker_ctx)));
+            });
+        if spawned.is_err() {
+            // Can't get a thread: fall back to collecting inline.
+            let _ = tx.send((index, module.collect(&ctx)));
+        }
+    }
+    drop(tx);
+
+    let mut outcomes: Vec<Outcome> = modules.iter().map(|_| Outcome::TimedOut).collect();
+    let mut remaining = modules.len();
+    while remaining > 0 {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(timeout) {
+            Ok((index, value)) => {
+                // Only modules that made the deadline get their cache entries saved
+                ctx.cache.commit(modules[index].key());
+                outcomes[index] = Outcome::Ready(value);
+                remaining -= 1;
+            }
+            Err(_) => break,
+        }
+    }
+
+    if remaining > 0 {
+        helpers::kill_running_cmds();
+    }
+
+    outcomes
+}
+
+fn numbered<T: std::fmt::Display>(label: &str, items: &[T]) -> Vec<(String, String)> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let label = if items.len() > 1 {
+                format!("{} {}", label, i + 1)
+            } else {
+                label.to_string()
+            };
+            (label, item.to_string())
+        })
+        .collect()
+}
+
+/// Pick the modules to collect.
+///
+/// An explicit key list selects exactly those modules in that order, including
+/// optional ones; unknown keys are reported and skipped. Without a list, the
+/// default order is used and optional modules are only kept when
+/// `include_optional` is set.
+pub fn select_modules(keys: Option<&[String]>, include_optional: bool) -> Vec<Arc<dyn InfoModule>> {
+    let mut available = default_modules();
+
+    let Some(keys) = keys else {
+        available.retain(|m| include_optional || !m.optional());
+        return available;
+    };
+
+    let mut selected = Vec::with_capacity(keys.len());
+    for key in keys {
+        match available.iter().position(|m| m.key() == key) {
+            Some(index) => selected.push(available.remove(index)),
+            None if selected.iter().any(|m| m.key() == key) => {
+                eprintln!("Warning: module '{}' is listed more than once", key)
+            }
+            None => eprintln!("Warning: unknown module '{}' in config", key),
+        }
+    }
+    selected
+}
+
+/// A single piece of system information.
+///
+/// Implementors collect a structured value and provide the label used when
+/// rendering it. Modules must be `Send + Sync` because [`collect_all`] runs
+/// each one on its own thread; collection itself only borrows the [`Context`].
+pub trait InfoModule: Send + Sync {
+    /// Stable identifier, used as the JSON key.
+    fn key(&self) -> &'static str;
+
+    /// Default label shown in text output.
+    fn label(&self) -> &'static str;
+
+    /// Collect the value, or `None` when there is nothing to show.
+    fn collect(&self, ctx: &Context) -> Option<Value>;
+
+    /// Optional modules are only shown with `--all` or when listed explicitly.
+    fn optional(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_value_lines() {
+        let value = Value::Text("6.18.3".to_string());
+        assert_eq!(
+            value.lines("Kernel"),
+            vec![("Kernel".to_string(), "6.18.3".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_list_values_are_numbered() {
+        let monitor = MonitorInfo::new(
+            "PG32UCDM",
+            "3840x2160".to_string(),
+            "240 Hz".to_string(),
+            true,
+            None,
+        );
+        let single = Value::Monitors(vec![monitor.clone()]);
+        assert_eq!(
+            single.lines("Display"),
+            vec![(
+                "Display".to_string(),
+                "(PG32UCDM) 3840x2160 @ 240 Hz [HDR]".to_string()
+            )]
+        );
+
+        let double = Value::Monitors(vec![monitor.clone(), monitor]);
+        let labels: Vec<String> = double.lines("Display").into_iter().map(|l| l.0).collect();
+        assert_eq!(labels, vec!["Display 1", "Display 2"]);
+    }
+
+    #[test]
+    fn test_empty_gpu_list_renders_unknown() {
+        assert_eq!(
+            Value::Gpus(Vec::new()).lines("GPU"),
+            vec![("GPU".to_string(), "Unknown".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_select_modules_default_skips_optional() {
+        let keys: Vec<&str> = select_modules(None, false)
+            .iter()
+            .map(|m| m.key())
+            .collect();
+        assert!(!keys.contains(&"locale"));
+        assert!(keys.contains(&"kernel"));
+
+        let all = select_modules(None, true);
+        assert!(all.iter().any(|m| m.key() == "locale"));
+    }
+
+    #[test]
+    fn test_select_modules_explicit_order() {
+        let keys = vec![
+            "locale".to_string(),
+            "cpu".to_string(),
+            "bogus".to_string(),
+            "kernel".to_string(),
+        ];
+        let selected: Vec<&str> = select_modules(Some(&keys), false)
+            .iter()
+            .map(|m| m.key())
+            .collect();
+        assert_eq!(selected, vec!["locale", "cpu", "kernel"]);
+    }
+
+    struct Sleepy(&'static str, Duration);
+
+    impl InfoModule for Sleepy {
+        fn key(&self) -> &'static str {
+            self.0
+        }
+        fn label(&self) -> &'static str {
+            "Sleepy"
+        }
+        fn collect(&self, _ctx: &Context) -> Option<Value> {
+            thread::sleep(self.1);
+            Some(Value::Text(self.0.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_collect_all_respects_deadline() {
+        let ctx = Arc::new(Context {
+            sys: System::new(),
+            cache: Cache::disabled(),
+            root: Sysroot::default(),
+            cmd: Box::new(crate::info::ReplayRunner::new("/nonexistent")),
+            config: Config::default(),
+        });
+        let modules: Vec<Arc<dyn InfoModule>> = vec![
+            Arc::new(Sleepy("slow", Duration::from_secs(5))),
+            Arc::new(Sleepy("fast", Duration::from_millis(1))),
+        ];
+
+        let start = Instant::now();
+        let outcomes = collect_all(ctx, &modules, Duration::from_millis(200));
+        assert!(
+            start.elapsed() < Duration::from_secs(2),
+            "deadline not honored, took {:?}",
+            start.elapsed()
+        );
+
+        assert!(outcomes[0].is_timed_out());
+        assert!(matches!(&outcomes[1], Outcome::Ready(Some(Value::Text(t))) if t == "fast"));
+        assert_eq!(serde_json::to_string(&outcomes[0]).unwrap(), "null");
+    }
+
+    /// Stores a cache entry, then sleeps before returning.
+    struct Caching(&'static str, Duration);
+
+    impl InfoModule for Caching {
+        fn key(&self) -> &'static str {
+            self.0
+        }
+        fn label(&self) -> &'static str {
+            "Caching"
+        }
+        fn collect(&self, ctx: &Context) -> Option<Value> {
+            let value: String =
+                ctx.cache
+                    .get_or_insert_with(self.0, Duration::from_secs(60), "", || self.0.to_string());
+            thread::sleep(self.1);
+            Some(Value::Text(value))
+        }
+    }
+
+    #[test]
+    fn test_timed_out_modules_are_not_cached() {
+        let ctx = Arc::new(Context {
+            sys: System::new(),
+            cache: Cache::open_at(None, crate::cache::CacheMode::Normal),
+            root: Sysroot::default(),
+            cmd: Box::new(crate::info::ReplayRunner::new("/nonexistent")),
+            config: Config::default(),
+        });
+        let modules: Vec<Arc<dyn InfoModule>> = vec![
+            Arc::new(Caching("slow", Duration::from_secs(5))),
+            Arc::new(Caching("fast", Duration::from_millis(1))),
+        ];
+        let outcomes = collect_all(Arc::clone(&ctx), &modules, Duration::from_millis(200));
+        assert!(outcomes[0].is_timed_out());
+
+        let cached = |field: &str| -> String {
+            ctx.cache
+                .get_or_insert_with(field, Duration::from_secs(60), "", || "miss".to_string())
+        };
+        assert_eq!(cached("fast"), "fast");
+        assert_eq!(cached("slow"), "miss");
+    }
+
+    #[test]
+    fn test_failed_public_ip_lookup_is_not_cached() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        // Drops the first connection, answers the second
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = Config::default();
+        config.public_ip.enabled = true;
+        config.public_ip.url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            drop(listener.accept().unwrap());
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            stream
+                .write_all(b"HTTP/1.0 200 OK\r\n\r\n203.0.113.7\n")
+                .unwrap();
+        });
+
+        let ctx = Context {
+            sys: System::new(),
+            cache: Cache::open_at(None, crate::cache::CacheMode::Normal),
+            // The cache key includes the local IP; read it from a fixture
+            root: crate::info::fixtures::root("fedora-vm"),
+            cmd: Box::new(crate::info::ReplayRunner::new("/nonexistent")),
+            config,
+        };
+        assert!(builtin::PublicIp.collect(&ctx).is_none());
+        let second = builtin::PublicIp.collect(&ctx);
+        server.join().unwrap();
+        assert!(matches!(second, Some(Value::PublicIp(info)) if info.to_string() == "203.0.113.7"));
+    }
+
+    #[test]
+    fn test_host_leaves_virtualization_to_its_own_line() {
+        let host = |modules: Option<&[&str]>| {
+            let ctx = Context {
+                sys: System::new(),
+                cache: Cache::disabled(),
+                root: crate::info::fixtures::root("fedora-vm"),
+                cmd: Box::new(crate::info::ReplayRunner::new("/nonexistent")),
+                config: Config {
+                    modules: modules.map(|keys| keys.iter().map(|k| k.to_string()).collect()),
+                    ..Config::default()
+                },
+            };
+            match builtin::Host.collect(&ctx) {
+                Some(Value::Text(text)) => text,
+                other => panic!("unexpected host value {:?}", other.is_some()),
+            }
+        };
+        let model = "Standard PC (Q35 + ICH9, 2009) pc-q35-9.1";
+        assert_eq!(host(None), format!("{} - KVM virtual machine", model));
+        assert_eq!(
+            host(Some(&["host", "kernel"])),
+            format!("{} - KVM virtual machine", model)
+        );
+        assert_eq!(host(Some(&["host", "virtualization"])), model);
+    }
+
+    #[test]
+    fn test_collect_all_runs_modules_concurrently() {
+        let ctx = Arc::new(Context {
+            sys: System::new(),
+            cache: Cache::disabled(),
+            root: Sysroot::default(),
+            cmd: Box::new(crate::info::ReplayRunner::new("/nonexistent")),
+            config: Config::default(),
+        });
+        let modules: Vec<Arc<dyn InfoModule>> = (0..4)
+            .map(|_| Arc::new(Sleepy("nap", Duration::from_millis(300))) as Arc<dyn InfoModule>)
+            .collect();
+
+        let start = Instant::now();
+        let outcomes = collect_all(ctx, &modules, Duration::from_secs(5));
+        assert!(outcomes.iter().all(|o| !o.is_timed_out()));
+        assert!(
+            start.elapsed() < Duration::from_millis(1000),
+            "modules ran serially, took {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_default_module_keys_are_unique() {
+        let modules = default_modules();
+        let mut keys: Vec<&str> = modules.iter().map(|m| m.key()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), modules.len());
+    }
+}
@@ -1,32 +1,38 @@
-use crate::info::MonitorInfo;
+use crate::modules::Outcome;
+use serde::ser::{Serialize, SerializeMap, Serializer};
 
 /// Collected system information data structure.
 /// Enables separation of data collection from rendering,
-/// and future JSON output support.
-#[allow(dead_code)]
+/// and backs the `--format json` output.
+///
+/// Serializes as one flat JSON object: the identity fields first, then one
+/// key per module in display order. Modules that found nothing, or missed the
+/// collection deadline, are `null`; the keys of the latter are listed under
+/// `timed_out`.
+#[derive(Debug)]
 pub struct SystemInfo {
     pub username: String,
     pub hostname: String,
-    pub os_name: String,
     pub distro_id: String,
-    pub kernel: String,
-    pub uptime: String,
-    pub packages: String,
-    pub shell: String,
-    pub shell_theme: Option<String>,
-    pub de: String,
-    pub wm: String,
-    pub terminal: String,
-    pub terminal_font: Option<String>,
-    pub multiplexer: Option<String>,
-    pub editor: Option<String>,
-    pub cpu: String,
-    pub gpus: Vec<String>,
-    pub memory: String,
-    pub swap: Option<String>,
-    pub disks: Vec<String>,
-    pub monitors: Vec<MonitorInfo>,
-    pub local_ip: String,
-    pub locale: String,
-    pub host: Option<String>,
+    pub modules: Vec<(&'static str, Outcome)>,
+}
+
+impl Serialize for SystemInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(4 + self.modules.len()))?;
+        map.serialize_entry("username", &self.username)?;
+        map.serialize_entry("hostname", &self.hostname)?;
+        map.serialize_entry("distro_id", &self.distro_id)?;
+        for (key, value) in &self.modules {
+            map.serialize_entry(key, value)?;
+        }
+        let timed_out: Vec<&str> = self
+            .modules
+            .iter()
+            .filter(|(_, outcome)| outcome.is_timed_out())
+            .map(|(key, _)| *key)
+            .collect();
+        map.serialize_entry("timed_out", &timed_out)?;
+        map.end()
+    }
 }
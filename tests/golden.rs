@@ -0,0 +1,96 @@
+//! Golden-output tests: run ghostfetch against each captured machine in
+//! `tests/fixtures/<machine>/root`, with helper commands replayed from
+//! `tests/fixtures/<machine>/commands`, and compare its JSON with
+//! `tests/fixtures/<machine>/expected.json`.
+//!
+//! Set `GHOSTFETCH_BLESS=1` to rewrite the expected files after an intended
+//! output change.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn run_fixture(machine: &Path) -> serde_json::Value {
+    let fixtures = fixtures_dir();
+    let output = Command::new(env!("CARGO_BIN_EXE_ghostfetch"))
+        .arg("--sysroot")
+        .arg(machine.join("root"))
+        .arg("--replay-commands")
+        .arg(machine.join("commands"))
+        .arg("--config")
+        .arg(fixtures.join("golden.toml"))
+        .args(["--format", "json", "--no-cache", "--timeout", "30000"])
+        .env_remove("GHOSTFETCH_SYSROOT")
+        // Per-user data comes from the machine's captured home, if any
+        .env("HOME", machine.join("home"))
+        // Keep the monitor fallback from depending on the test environment
+        .env_remove("DISPLAY")
+        .env_remove("WAYLAND_DISPLAY")
+        .env_remove("XDG_CURRENT_DESKTOP")
+        // Logs such as dpkg.log carry local times
+        .env("TZ", "UTC")
+        .output()
+        .expect("ghostfetch should run");
+    assert!(
+        output.status.success(),
+        "ghostfetch failed for {}: {}",
+        machine.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let mut json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("output should be valid JSON");
+    // The user running the tests isn't part of the captured machine
+    json.as_object_mut()
+        .expect("output should be a JSON object")
+        .remove("username");
+    json
+}
+
+#[test]
+fn golden_outputs_match() {
+    let bless = std::env::var_os("GHOSTFETCH_BLESS").is_some();
+    let mut machines: Vec<PathBuf> = fs::read_dir(fixtures_dir())
+        .expect("fixtures directory should exist")
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join("root").is_dir())
+        .collect();
+    machines.sort();
+    assert!(!machines.is_empty(), "no fixture machines found");
+
+    let mut failures = Vec::new();
+    for machine in &machines {
+        let actual = run_fixture(machine);
+        let expected_path = machine.join("expected.json");
+
+        if bless {
+            let pretty = serde_json::to_string_pretty(&actual).unwrap();
+            fs::write(&expected_path, pretty + "\n").expect("cannot write expected.json");
+            continue;
+        }
+
+        let expected: serde_json::Value = fs::read_to_string(&expected_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or(serde_json::Value::Null);
+        if actual != expected {
+            failures.push(format!(
+                "{}:\n--- expected\n{}\n+++ actual\n{}",
+                machine.display(),
+                serde_json::to_string_pretty(&expected).unwrap(),
+                serde_json::to_string_pretty(&actual).unwrap()
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "golden output mismatch (rerun with GHOSTFETCH_BLESS=1 if intended):\n{}",
+        failures.join("\n\n")
+    );
+}